@@ -1,6 +1,7 @@
-use nalgebra_glm::{Vec3, rotate_vec3};
+use nalgebra_glm::Vec3;
 use std::f32::consts::PI;
 
+/// Cámara orbital definida por su posición, el punto al que mira y el eje "arriba".
 pub struct Camera {
     pub eye: Vec3,
     pub center: Vec3,
@@ -25,7 +26,7 @@ impl Camera {
         }
     }
 
-    // Orbita alrededor del punto central, ajustando yaw y pitch
+    /// Orbita alrededor del punto central, ajustando yaw y pitch
     pub fn orbit(&mut self, delta_yaw: f32, delta_pitch: f32) {
         let radius_vector = self.eye - self.center;
         let radius = radius_vector.magnitude();
@@ -46,7 +47,7 @@ impl Camera {
         self.has_changed = true;
     }
 
-    // Cambia el centro de la cámara moviéndolo en la dirección especificada
+    /// Cambia el centro de la cámara moviéndolo en la dirección especificada
     pub fn move_center(&mut self, direction: Vec3) {
         let movement = direction.normalize() * 0.1; // Adjust the factor for movement speed
         self.center += movement;
@@ -55,14 +56,14 @@ impl Camera {
         self.has_changed = true;
     }
 
-    // Acerca o aleja la cámara hacia el punto central
+    /// Acerca o aleja la cámara hacia el punto central
     pub fn zoom(&mut self, delta: f32) {
         let direction = (self.center - self.eye).normalize();
         self.eye += direction * delta;
         self.has_changed = true;
     }
 
-    // Verifica si la cámara ha cambiado y resetea el estado
+    /// Verifica si la cámara ha cambiado y resetea el estado
     pub fn check_if_changed(&mut self) -> bool {
         if self.has_changed {
             self.has_changed = false;
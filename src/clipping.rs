@@ -0,0 +1,135 @@
+use nalgebra_glm::{Mat4, Vec3, Vec4};
+use crate::vertex::Vertex;
+
+// Planos del volumen de visión en espacio de recorte (convención OpenGL, -w <= x,y,z <= w).
+// Cada plano se evalúa como dot(plano, posición); el punto está dentro si el valor es >= 0.
+const CLIP_PLANES: [Vec4; 6] = [
+    Vec4::new(0.0, 0.0, 1.0, 1.0),  // Cercano: z + w >= 0
+    Vec4::new(0.0, 0.0, -1.0, 1.0), // Lejano:  w - z >= 0
+    Vec4::new(1.0, 0.0, 0.0, 1.0),  // Izquierdo
+    Vec4::new(-1.0, 0.0, 0.0, 1.0), // Derecho
+    Vec4::new(0.0, 1.0, 0.0, 1.0),  // Inferior
+    Vec4::new(0.0, -1.0, 0.0, 1.0), // Superior
+];
+
+/// Recorta un triángulo en espacio de recorte contra los seis planos del frustum
+/// (Sutherland-Hodgman) y devuelve el polígono resultante ya re-triangulado en abanico.
+/// Los vértices devueltos siguen en espacio de recorte; falta la división por w.
+pub fn clip_triangle(v1: &Vertex, v2: &Vertex, v3: &Vertex) -> Vec<[Vertex; 3]> {
+    let vertices = [v1, v2, v3];
+    let mut all_inside = true;
+
+    for plane in &CLIP_PLANES {
+        let distances = vertices.map(|v| plane.dot(&v.clip_position));
+
+        // Rechazo trivial: todo el triángulo queda del lado exterior de un mismo plano
+        if distances.iter().all(|&d| d < 0.0) {
+            return Vec::new();
+        }
+        if distances.iter().any(|&d| d < 0.0) {
+            all_inside = false;
+        }
+    }
+
+    if all_inside {
+        return vec![[v1.clone(), v2.clone(), v3.clone()]];
+    }
+
+    let mut polygon = vec![v1.clone(), v2.clone(), v3.clone()];
+
+    for plane in &CLIP_PLANES {
+        polygon = clip_polygon_against_plane(&polygon, plane);
+        if polygon.len() < 3 {
+            return Vec::new();
+        }
+    }
+
+    (1..polygon.len() - 1)
+        .map(|i| [polygon[0].clone(), polygon[i].clone(), polygon[i + 1].clone()])
+        .collect()
+}
+
+fn clip_polygon_against_plane(polygon: &[Vertex], plane: &Vec4) -> Vec<Vertex> {
+    let mut output = Vec::with_capacity(polygon.len() + 1);
+
+    for i in 0..polygon.len() {
+        let current = &polygon[i];
+        let next = &polygon[(i + 1) % polygon.len()];
+
+        let current_distance = plane.dot(&current.clip_position);
+        let next_distance = plane.dot(&next.clip_position);
+
+        if current_distance >= 0.0 {
+            output.push(current.clone());
+        }
+
+        // La arista cruza el plano: se agrega el punto de intersección
+        if (current_distance >= 0.0) != (next_distance >= 0.0) {
+            let t = current_distance / (current_distance - next_distance);
+            output.push(current.lerp(next, t));
+        }
+    }
+
+    output
+}
+
+/// División de perspectiva y transformación de viewport para un vértice ya recortado
+pub fn to_screen(vertex: &Vertex, viewport_matrix: &Mat4) -> Vertex {
+    let clip = vertex.clip_position;
+    let w = clip.w;
+    let ndc = Vec4::new(clip.x / w, clip.y / w, clip.z / w, 1.0);
+    let screen = viewport_matrix * ndc;
+
+    Vertex {
+        transformed_position: Vec3::new(screen.x, screen.y, screen.z),
+        inv_w: 1.0 / w,
+        ..vertex.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(x: f32, y: f32, z: f32, w: f32) -> Vertex {
+        Vertex { clip_position: Vec4::new(x, y, z, w), ..Vertex::default() }
+    }
+
+    fn positions(triangles: &[[Vertex; 3]]) -> Vec<[Vec4; 3]> {
+        triangles.iter().map(|triangle| triangle.clone().map(|vertex| vertex.clip_position)).collect()
+    }
+
+    #[test]
+    fn triangles_inside_the_frustum_are_unchanged() {
+        let triangle = [at(-0.5, -0.5, 0.0, 1.0), at(0.5, -0.5, 0.5, 2.0), at(0.0, 0.5, -0.5, 1.0)];
+        let clipped = clip_triangle(&triangle[0], &triangle[1], &triangle[2]);
+
+        assert_eq!(positions(&clipped), vec![triangle.map(|vertex| vertex.clip_position)]);
+    }
+
+    #[test]
+    fn vertices_behind_the_near_plane_are_cut() {
+        // Uno detrás (z < -w, dentro de los otros planos): queda un cuadrilátero, dos
+        // triángulos
+        let clipped = clip_triangle(&at(-0.5, -0.5, 0.5, 1.0), &at(0.5, -0.5, 0.5, 1.0), &at(0.0, 0.2, -1.0, 0.5));
+        assert_eq!(clipped.len(), 2);
+        assert!(clipped.iter().flatten().all(|vertex| vertex.clip_position.w > 0.0));
+        assert!(clipped.iter().flatten().all(|vertex| vertex.clip_position.z + vertex.clip_position.w >= -1e-6));
+
+        // Detrás de la cámara (w < 0): lo que queda está todo delante
+        let clipped = clip_triangle(&at(-0.5, -0.5, 0.5, 1.0), &at(0.5, -0.5, 0.5, 1.0), &at(0.0, 0.0, -3.0, -1.0));
+        assert!(!clipped.is_empty());
+        assert!(clipped.iter().flatten().all(|vertex| vertex.clip_position.w > 0.0));
+
+        // Dos detrás: queda un triángulo
+        let clipped = clip_triangle(&at(-0.5, -0.5, 0.5, 1.0), &at(0.2, -0.2, -1.0, 0.5), &at(0.0, 0.2, -1.0, 0.5));
+        assert_eq!(clipped.len(), 1);
+        assert!(clipped.iter().flatten().all(|vertex| vertex.clip_position.w > 0.0));
+    }
+
+    #[test]
+    fn triangles_outside_the_frustum_are_dropped() {
+        let clipped = clip_triangle(&at(2.0, 0.0, 0.0, 1.0), &at(3.0, 0.5, 0.0, 1.0), &at(2.5, -0.5, 0.0, 1.0));
+        assert!(clipped.is_empty());
+    }
+}
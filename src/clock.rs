@@ -0,0 +1,150 @@
+/// Paso fijo por defecto: 60 pasos por segundo simulado.
+pub const DEFAULT_STEP: f64 = 1.0 / 60.0;
+
+/// Escalas de tiempo que recorren `speed_up` y `slow_down`.
+pub const TIME_SCALES: [f64; 5] = [1.0, 10.0, 100.0, 1000.0, 10000.0];
+
+// Con más pasos pendientes que esto (por ejemplo después de una pausa larga del
+// proceso) se descarta el resto en lugar de intentar alcanzarlo
+const MAX_STEPS_PER_FRAME: u32 = 100_000;
+
+/// Reloj de la simulación en segundos, con paso fijo.
+///
+/// El tiempo real de cada frame, multiplicado por la escala, se acumula y se consume en
+/// pasos de `step` segundos; lo que sobra queda para el frame siguiente. Así la
+/// simulación avanza igual sin importar los FPS, y al dibujar se interpola entre los
+/// dos últimos pasos con [`SimClock::alpha`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct SimClock {
+    pub time: f64, // Segundos simulados después del último paso
+    pub step: f64,
+    pub scale: f64,
+    pub paused: bool,
+    pub reversed: bool,
+    previous_time: f64, // Antes del último paso
+    accumulator: f64,
+}
+
+impl Default for SimClock {
+    fn default() -> Self {
+        SimClock::new(DEFAULT_STEP)
+    }
+}
+
+impl SimClock {
+    pub fn new(step: f64) -> Self {
+        SimClock {
+            time: 0.0,
+            step,
+            scale: 1.0,
+            paused: false,
+            reversed: false,
+            previous_time: 0.0,
+            accumulator: 0.0,
+        }
+    }
+
+    /// Acumula `real_seconds` de tiempo real y devuelve cuántos pasos hay que simular.
+    /// En pausa no acumula nada.
+    pub fn advance(&mut self, real_seconds: f64) -> u32 {
+        if self.paused {
+            return 0;
+        }
+        self.accumulator += real_seconds * self.scale;
+        let steps = (self.accumulator / self.step).floor();
+        self.accumulator -= steps * self.step;
+        (steps as u32).min(MAX_STEPS_PER_FRAME)
+    }
+
+    /// Duración con signo de un paso: negativa cuando el tiempo corre hacia atrás.
+    pub fn delta(&self) -> f32 {
+        if self.reversed { -self.step as f32 } else { self.step as f32 }
+    }
+
+    /// Registra que se simuló un paso.
+    pub fn tick(&mut self) {
+        self.previous_time = self.time;
+        self.time += self.delta() as f64;
+    }
+
+    /// Fracción del paso siguiente que ya pasó, en [0, 1): cuánto hay que avanzar desde
+    /// el penúltimo estado hacia el último al dibujar.
+    pub fn alpha(&self) -> f32 {
+        (self.accumulator / self.step) as f32
+    }
+
+    /// Tiempo continuo del frame que se dibuja, interpolado igual que los cuerpos.
+    pub fn render_time(&self) -> f32 {
+        (self.previous_time + (self.time - self.previous_time) * self.alpha() as f64) as f32
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+    }
+
+    pub fn toggle_reverse(&mut self) {
+        self.reversed = !self.reversed;
+    }
+
+    /// Pasa a la escala siguiente de [`TIME_SCALES`], sin pasar de la última.
+    pub fn speed_up(&mut self) {
+        self.scale = TIME_SCALES.into_iter().find(|&scale| scale > self.scale).unwrap_or(self.scale);
+    }
+
+    /// Pasa a la escala anterior de [`TIME_SCALES`], sin bajar de la primera.
+    pub fn slow_down(&mut self) {
+        self.scale = TIME_SCALES.into_iter().rev().find(|&scale| scale < self.scale).unwrap_or(self.scale);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn steps_do_not_depend_on_frame_rate() {
+        // Un segundo real en frames de 30 FPS y de 144 FPS da los mismos 60 pasos
+        for fps in [30.0, 144.0] {
+            let mut clock = SimClock::default();
+            let steps: u32 = (0..fps as usize).map(|_| clock.advance(1.0 / fps)).sum();
+            assert!((59..=60).contains(&steps), "{} FPS: {} pasos", fps, steps);
+        }
+    }
+
+    #[test]
+    fn leftover_time_becomes_the_interpolation_factor() {
+        let mut clock = SimClock::new(0.1);
+        assert_eq!(clock.advance(0.25), 2);
+        assert!((clock.alpha() - 0.5).abs() < 1e-5);
+
+        clock.tick();
+        clock.tick();
+        assert!((clock.render_time() - 0.15).abs() < 1e-5);
+    }
+
+    #[test]
+    fn pause_reverse_and_scale() {
+        let mut clock = SimClock::new(0.1);
+
+        clock.toggle_pause();
+        assert_eq!(clock.advance(1.0), 0);
+        clock.toggle_pause();
+
+        clock.speed_up();
+        assert_eq!(clock.scale, 10.0);
+        assert_eq!(clock.advance(1.0), 100);
+
+        clock.toggle_reverse();
+        clock.tick();
+        assert!((clock.time + 0.1).abs() < 1e-6);
+
+        for _ in 0..10 {
+            clock.speed_up();
+        }
+        assert_eq!(clock.scale, 10000.0);
+        for _ in 0..10 {
+            clock.slow_down();
+        }
+        assert_eq!(clock.scale, 1.0);
+    }
+}
@@ -1,6 +1,7 @@
 use std::fmt;
+use nalgebra_glm::Vec3;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Color {
     r: u8,
     g: u8,
@@ -23,11 +24,22 @@ impl Color {
         Color { r: 0, g: 0, b: 0 }
     }
 
-    pub fn to_hex(&self) -> u32 {
+    pub fn to_hex(self) -> u32 {
         ((self.r as u32) << 16) | ((self.g as u32) << 8) | (self.b as u32)
     }
 
-    // Linear interpolation between two colors
+    /// Canales en [0, 1], para operar sobre la luz en punto flotante
+    pub fn to_vec3(self) -> Vec3 {
+        Vec3::new(self.r as f32, self.g as f32, self.b as f32) / 255.0
+    }
+
+    /// Inversa de `to_vec3`; los valores fuera de [0, 1] se recortan
+    pub fn from_vec3(rgb: Vec3) -> Self {
+        let channel = |value: f32| (value * 255.0).round().clamp(0.0, 255.0) as u8;
+        Color { r: channel(rgb.x), g: channel(rgb.y), b: channel(rgb.z) }
+    }
+
+    /// Linear interpolation between two colors
     pub fn lerp(&self, other: &Color, t: f32) -> Self {
         let t = t.clamp(0.0, 1.0);
         Color {
@@ -62,9 +74,9 @@ impl Color {
     }
     
     pub fn blend_subtract(&self, blend: &Color) -> Color {
-        let r = (self.r as i16 - blend.r as i16).max(0).min(255) as u8;
-        let g = (self.g as i16 - blend.g as i16).max(0).min(255) as u8;
-        let b = (self.b as i16 - blend.b as i16).max(0).min(255) as u8;
+        let r = (self.r as i16 - blend.r as i16).clamp(0, 255) as u8;
+        let g = (self.g as i16 - blend.g as i16).clamp(0, 255) as u8;
+        let b = (self.b as i16 - blend.b as i16).clamp(0, 255) as u8;
 
         Color::new(r, g, b)
     }
@@ -0,0 +1,304 @@
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use crate::framebuffer::Framebuffer;
+
+/// Formatos de imagen soportados para exportar frames
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ImageFormat {
+    Ppm, // Sin dependencias; el depth buffer se guarda como PGM de 16 bits
+    Png,
+}
+
+impl ImageFormat {
+    pub fn from_name(name: &str) -> Option<ImageFormat> {
+        match name.to_ascii_lowercase().as_str() {
+            "ppm" => Some(ImageFormat::Ppm),
+            "png" => Some(ImageFormat::Png),
+            _ => None,
+        }
+    }
+
+    pub fn color_extension(self) -> &'static str {
+        match self {
+            ImageFormat::Ppm => "ppm",
+            ImageFormat::Png => "png",
+        }
+    }
+
+    pub fn depth_extension(self) -> &'static str {
+        match self {
+            ImageFormat::Ppm => "pgm",
+            ImageFormat::Png => "png",
+        }
+    }
+}
+
+/// Guarda el buffer activo (ya resuelto) como imagen RGB de 8 bits
+pub fn save_color(framebuffer: &Framebuffer, path: &Path, format: ImageFormat) -> io::Result<()> {
+    save_pixels(framebuffer.get_active_buffer(), framebuffer.width, framebuffer.height, path, format)
+}
+
+/// Guarda píxeles 0xRRGGBB como imagen RGB de 8 bits
+pub fn save_pixels(pixels: &[u32], width: usize, height: usize, path: &Path, format: ImageFormat) -> io::Result<()> {
+    let rgb: Vec<u8> = pixels
+        .iter()
+        .flat_map(|&color| [(color >> 16) as u8, (color >> 8) as u8, color as u8])
+        .collect();
+
+    let mut writer = BufWriter::new(File::create(path)?);
+    match format {
+        ImageFormat::Ppm => {
+            write!(writer, "P6\n{} {}\n255\n", width, height)?;
+            writer.write_all(&rgb)?;
+        }
+        ImageFormat::Png => {
+            write_png(&mut writer, width, height, png::ColorType::Rgb, png::BitDepth::Eight, &rgb)?;
+        }
+    }
+    writer.flush()
+}
+
+/// Guarda el zbuffer como escala de grises de 16 bits (negro = cerca, blanco = lejos o vacío).
+/// Con MSAA se usa la primera muestra de cada píxel.
+pub fn save_depth(framebuffer: &Framebuffer, path: &Path, format: ImageFormat) -> io::Result<()> {
+    let samples = framebuffer.msaa().sample_count();
+    // Big-endian, como piden tanto PGM como PNG para 16 bits
+    let gray: Vec<u8> = framebuffer
+        .zbuffer
+        .iter()
+        .step_by(samples)
+        .flat_map(|&depth| depth_to_u16(depth).to_be_bytes())
+        .collect();
+
+    let mut writer = BufWriter::new(File::create(path)?);
+    match format {
+        ImageFormat::Ppm => {
+            write!(writer, "P5\n{} {}\n65535\n", framebuffer.width, framebuffer.height)?;
+            writer.write_all(&gray)?;
+        }
+        ImageFormat::Png => {
+            write_png(&mut writer, framebuffer.width, framebuffer.height, png::ColorType::Grayscale, png::BitDepth::Sixteen, &gray)?;
+        }
+    }
+    writer.flush()
+}
+
+// La profundidad guardada es z en NDC, en [-1, 1]; se lleva a [0, 65535]
+fn depth_to_u16(depth: f32) -> u16 {
+    if depth.is_finite() {
+        (((depth + 1.0) * 0.5).clamp(0.0, 1.0) * u16::MAX as f32).round() as u16
+    } else {
+        u16::MAX
+    }
+}
+
+fn write_png<W: Write>(
+    writer: W,
+    width: usize,
+    height: usize,
+    color_type: png::ColorType,
+    bit_depth: png::BitDepth,
+    data: &[u8],
+) -> io::Result<()> {
+    let mut encoder = png::Encoder::new(writer, width as u32, height as u32);
+    encoder.set_color(color_type);
+    encoder.set_depth(bit_depth);
+
+    let mut png_writer = encoder.write_header().map_err(io::Error::other)?;
+    png_writer.write_image_data(data).map_err(io::Error::other)
+}
+
+/// Escribe frames numerados (frame_00000.png, frame_00001.png, ...) en un directorio,
+/// para armar videos a partir de la secuencia
+pub struct FrameRecorder {
+    directory: PathBuf,
+    format: ImageFormat,
+    with_depth: bool,
+    next_frame: usize,
+}
+
+impl FrameRecorder {
+    pub fn new(directory: &Path, format: ImageFormat, with_depth: bool) -> io::Result<Self> {
+        fs::create_dir_all(directory)?;
+        Ok(FrameRecorder {
+            directory: directory.to_path_buf(),
+            format,
+            with_depth,
+            next_frame: 0,
+        })
+    }
+
+    pub fn record(&mut self, framebuffer: &Framebuffer) -> io::Result<()> {
+        let name = format!("frame_{:05}", self.next_frame);
+        save_color(framebuffer, &self.directory.join(format!("{}.{}", name, self.format.color_extension())), self.format)?;
+        if self.with_depth {
+            save_depth(framebuffer, &self.directory.join(format!("{}_depth.{}", name, self.format.depth_extension())), self.format)?;
+        }
+
+        self.next_frame += 1;
+        Ok(())
+    }
+}
+
+/// Guarda una captura en `directory` con el primer nombre libre (captura_000.png, ...)
+/// y devuelve la ruta usada
+pub fn save_screenshot(framebuffer: &Framebuffer, directory: &Path, format: ImageFormat, with_depth: bool) -> io::Result<PathBuf> {
+    fs::create_dir_all(directory)?;
+
+    let (path, depth_path) = (0..)
+        .map(|index| {
+            (
+                directory.join(format!("captura_{:03}.{}", index, format.color_extension())),
+                directory.join(format!("captura_{:03}_depth.{}", index, format.depth_extension())),
+            )
+        })
+        .find(|(path, _)| !path.exists())
+        .expect("no quedan nombres libres para la captura");
+
+    save_color(framebuffer, &path, format)?;
+    if with_depth {
+        save_depth(framebuffer, &depth_path, format)?;
+    }
+    Ok(path)
+}
+
+/// Lee una imagen PNG o PPM (P6) como píxeles 0xRRGGBB; devuelve (ancho, alto, píxeles)
+pub fn load_pixels(path: &Path) -> io::Result<(usize, usize, Vec<u32>)> {
+    let is_ppm = path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("ppm"));
+    if is_ppm {
+        return load_ppm(&fs::read(path)?);
+    }
+
+    let decoder = png::Decoder::new(File::open(path)?);
+    let mut reader = decoder.read_info().map_err(io::Error::other)?;
+    let mut data = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut data).map_err(io::Error::other)?;
+
+    if info.bit_depth != png::BitDepth::Eight {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "solo se soportan PNG de 8 bits"));
+    }
+    let channels = match info.color_type {
+        png::ColorType::Grayscale => 1,
+        png::ColorType::GrayscaleAlpha => 2,
+        png::ColorType::Rgb => 3,
+        png::ColorType::Rgba => 4,
+        png::ColorType::Indexed => {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "PNG con paleta no soportado"));
+        }
+    };
+
+    let pixels = data[..info.buffer_size()]
+        .chunks(channels)
+        .map(|pixel| {
+            let (r, g, b) = if channels < 3 { (pixel[0], pixel[0], pixel[0]) } else { (pixel[0], pixel[1], pixel[2]) };
+            ((r as u32) << 16) | ((g as u32) << 8) | b as u32
+        })
+        .collect();
+
+    Ok((info.width as usize, info.height as usize, pixels))
+}
+
+fn load_ppm(bytes: &[u8]) -> io::Result<(usize, usize, Vec<u32>)> {
+    let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+
+    // Cabecera: "P6", ancho, alto y valor máximo separados por espacios (con comentarios #)
+    let mut fields = Vec::new();
+    let mut position = 0;
+    while fields.len() < 4 {
+        while position < bytes.len() && bytes[position].is_ascii_whitespace() {
+            position += 1;
+        }
+        if position < bytes.len() && bytes[position] == b'#' {
+            while position < bytes.len() && bytes[position] != b'\n' {
+                position += 1;
+            }
+            continue;
+        }
+        let start = position;
+        while position < bytes.len() && !bytes[position].is_ascii_whitespace() {
+            position += 1;
+        }
+        if start == position {
+            return Err(invalid("cabecera PPM incompleta"));
+        }
+        fields.push(String::from_utf8_lossy(&bytes[start..position]).into_owned());
+    }
+    position += 1; // Un solo espacio separa la cabecera de los datos
+
+    if fields[0] != "P6" || fields[3] != "255" {
+        return Err(invalid("solo se soportan PPM binarios (P6) de 8 bits"));
+    }
+    let width: usize = fields[1].parse().map_err(|_| invalid("ancho PPM inválido"))?;
+    let height: usize = fields[2].parse().map_err(|_| invalid("alto PPM inválido"))?;
+
+    let data = bytes.get(position..position + width * height * 3).ok_or_else(|| invalid("datos PPM incompletos"))?;
+    let pixels = data
+        .chunks(3)
+        .map(|pixel| ((pixel[0] as u32) << 16) | ((pixel[1] as u32) << 8) | pixel[2] as u32)
+        .collect();
+
+    Ok((width, height, pixels))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::framebuffer::Msaa;
+
+    fn test_directory(name: &str) -> PathBuf {
+        let directory = std::env::temp_dir().join(format!("export_test_{}_{}", name, std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        directory
+    }
+
+    // Valores de 16 bits de una imagen de profundidad guardada, en el orden de los píxeles
+    fn load_depth(path: &Path, format: ImageFormat) -> (usize, usize, Vec<u16>) {
+        let (width, height, data) = match format {
+            ImageFormat::Ppm => {
+                let bytes = fs::read(path).unwrap();
+                let header = b"P5\n2 2\n65535\n";
+                assert!(bytes.starts_with(header));
+                (2, 2, bytes[header.len()..].to_vec())
+            }
+            ImageFormat::Png => {
+                let mut reader = png::Decoder::new(File::open(path).unwrap()).read_info().unwrap();
+                let mut data = vec![0; reader.output_buffer_size()];
+                let info = reader.next_frame(&mut data).unwrap();
+                assert_eq!((info.color_type, info.bit_depth), (png::ColorType::Grayscale, png::BitDepth::Sixteen));
+                data.truncate(info.buffer_size());
+                (info.width as usize, info.height as usize, data)
+            }
+        };
+        (width, height, data.chunks(2).map(|pair| u16::from_be_bytes([pair[0], pair[1]])).collect())
+    }
+
+    #[test]
+    fn saved_colors_load_back_unchanged() {
+        let directory = test_directory("color");
+        let mut framebuffer = Framebuffer::new(3, 2);
+        framebuffer.buffer1.copy_from_slice(&[0xFF0000, 0x00FF00, 0x0000FF, 0x123456, 0xABCDEF, 0x000000]);
+
+        for format in [ImageFormat::Png, ImageFormat::Ppm] {
+            let path = directory.join(format!("color.{}", format.color_extension()));
+            save_color(&framebuffer, &path, format).unwrap();
+            assert_eq!(load_pixels(&path).unwrap(), (3, 2, framebuffer.get_active_buffer().to_vec()));
+        }
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn depth_is_saved_as_16_bit_gray() {
+        let directory = test_directory("depth");
+        // Cerca, en el medio, lejos y fondo vacío; con MSAA solo cuenta la primera muestra
+        let mut framebuffer = Framebuffer::with_msaa(2, 2, Msaa::X2);
+        framebuffer.zbuffer.copy_from_slice(&[-1.0, 0.3, 0.0, 0.3, 1.0, 0.3, f32::INFINITY, 0.3]);
+
+        for format in [ImageFormat::Png, ImageFormat::Ppm] {
+            let path = directory.join(format!("profundidad.{}", format.depth_extension()));
+            save_depth(&framebuffer, &path, format).unwrap();
+            assert_eq!(load_depth(&path, format), (2, 2, vec![0, 32768, 65535, 65535]));
+        }
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
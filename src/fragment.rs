@@ -1,25 +1,47 @@
-
-use nalgebra_glm::{Vec2, Vec3};
+use nalgebra_glm::{Vec2, Vec3, Vec4};
 use crate::color::Color;
 
+/// Muestra de un triángulo en un píxel, con los atributos ya interpolados.
 pub struct Fragment {
     pub position: Vec2,
     pub color: Color,
     pub depth: f32,
-    pub normal: Vec3,
-    pub intensity: f32,
-    pub vertex_position: Vec3,
+    pub normal: Vec3, // En el mundo, normalizada
+    pub vertex_position: Vec3, // En el espacio del objeto
+    pub world_position: Vec3,
+    pub tex_coords: Vec2,
+    pub tex_coords_dx: Vec2, // Cambio de tex_coords al pasar al píxel de la derecha
+    pub tex_coords_dy: Vec2, // Y al de abajo; con ellos se elige el mipmap
+    pub tangent: Vec4, // En el mundo, hacia donde crece u; en w el sentido de la bitangente
 }
 
 impl Fragment {
-    pub fn new(x: f32, y: f32, color: Color, depth: f32, normal: Vec3, intensity: f32, vertex_position: Vec3,) -> Self {
+    pub fn new(x: f32, y: f32, color: Color, depth: f32, normal: Vec3, vertex_position: Vec3, world_position: Vec3) -> Self {
         Fragment {
             position: Vec2::new(x, y),
             color,
             depth,
             normal,
-            intensity,
-            vertex_position
+            vertex_position,
+            world_position,
+            tex_coords: Vec2::zeros(),
+            tex_coords_dx: Vec2::zeros(),
+            tex_coords_dy: Vec2::zeros(),
+            tangent: Vec4::zeros(),
         }
     }
+
+    /// Coordenadas de textura en el fragmento y sus derivadas en pantalla.
+    pub fn with_tex_coords(mut self, tex_coords: Vec2, dx: Vec2, dy: Vec2) -> Self {
+        self.tex_coords = tex_coords;
+        self.tex_coords_dx = dx;
+        self.tex_coords_dy = dy;
+        self
+    }
+
+    /// Tangente interpolada, para los mapas de normales (ver [`crate::maps`]).
+    pub fn with_tangent(mut self, tangent: Vec4) -> Self {
+        self.tangent = tangent;
+        self
+    }
 }
\ No newline at end of file
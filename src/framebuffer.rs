@@ -1,14 +1,115 @@
+use rayon::prelude::*;
+use crate::triangle::Bounds;
+
+/// Modo de antialiasing por multimuestreo
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Msaa {
+    Off,
+    X2,
+    X4,
+    X8,
+}
+
+impl Msaa {
+    pub fn sample_count(self) -> usize {
+        match self {
+            Msaa::Off => 1,
+            Msaa::X2 => 2,
+            Msaa::X4 => 4,
+            Msaa::X8 => 8,
+        }
+    }
+
+    /// Posiciones de las muestras relativas al centro del píxel, en dieciseisavos de
+    /// píxel (patrones estándar de Direct3D, con Y hacia abajo)
+    pub fn sample_offsets(self) -> &'static [(i32, i32)] {
+        match self {
+            Msaa::Off => &[(0, 0)],
+            Msaa::X2 => &[(4, 4), (-4, -4)],
+            Msaa::X4 => &[(-2, -6), (6, -2), (-6, 2), (2, 6)],
+            Msaa::X8 => &[(1, -3), (-1, 3), (5, 1), (-3, -5), (-5, 5), (-7, -1), (3, 7), (7, -7)],
+        }
+    }
+
+    pub fn from_samples(samples: usize) -> Option<Msaa> {
+        match samples {
+            0 | 1 => Some(Msaa::Off),
+            2 => Some(Msaa::X2),
+            4 => Some(Msaa::X4),
+            8 => Some(Msaa::X8),
+            _ => None,
+        }
+    }
+
+    /// Siguiente modo, para alternar entre ellos en tiempo de ejecución
+    pub fn next(self) -> Msaa {
+        match self {
+            Msaa::Off => Msaa::X2,
+            Msaa::X2 => Msaa::X4,
+            Msaa::X4 => Msaa::X8,
+            Msaa::X8 => Msaa::Off,
+        }
+    }
+}
+
+/// Destino del render: doble buffer de color 0xRRGGBB y zbuffer, con MSAA opcional.
 pub struct Framebuffer {
     pub width: usize,
     pub height: usize,
     pub buffer1: Vec<u32>,
     pub buffer2: Vec<u32>,
+    // Profundidad por muestra: el píxel (x, y) ocupa los índices
+    // (y * width + x) * samples .. + samples
     pub zbuffer: Vec<f32>,
+    // Color por muestra con MSAA; vacío sin MSAA, donde se escribe directo al buffer activo
+    sample_buffer: Vec<u32>,
+    msaa: Msaa,
     background_color: u32,
     current_color: u32,
     active_buffer: bool,
 }
 
+/// Región rectangular del framebuffer con acceso exclusivo a sus filas de color y
+/// profundidad. Cada tile se puede rasterizar en un hilo distinto sin bloqueos.
+pub struct Tile<'a> {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+    pub msaa: Msaa,
+    samples: usize,
+    color_rows: Vec<&'a mut [u32]>,
+    depth_rows: Vec<&'a mut [f32]>,
+}
+
+impl Tile<'_> {
+    pub fn bounds(&self) -> Bounds {
+        Bounds::new(
+            self.x as i32,
+            self.y as i32,
+            (self.x + self.width) as i32,
+            (self.y + self.height) as i32,
+        )
+    }
+
+    /// Prueba de profundidad temprana de una muestra, antes de sombrear el fragmento
+    pub fn depth_test(&self, x: usize, y: usize, sample: usize, depth: f32) -> bool {
+        self.depth_rows[y - self.y][(x - self.x) * self.samples + sample] > depth
+    }
+
+    /// Escribe un color en una muestra con prueba de profundidad; (x, y) son coordenadas
+    /// de pantalla
+    pub fn set_color(&mut self, x: usize, y: usize, sample: usize, color: u32, depth: f32) {
+        let (local_x, local_y) = ((x - self.x) * self.samples + sample, y - self.y);
+        let stored_depth = &mut self.depth_rows[local_y][local_x];
+
+        if *stored_depth > depth {
+            self.color_rows[local_y][local_x] = color;
+            *stored_depth = depth;
+        }
+    }
+}
+
 impl Framebuffer {
 
     pub fn draw_line(&mut self, x1: usize, y1: usize, x2: usize, y2: usize) {
@@ -51,30 +152,60 @@ impl Framebuffer {
         }
     }    
 
+    /// Escribe el color en todas las muestras del píxel que pasen la prueba de profundidad
     pub fn set_color_at_index(&mut self, index: usize, color: u32, depth: f32) {
-        if index < self.zbuffer.len() && self.zbuffer[index] > depth {
-            if color != 0x0 { // Previene el uso de un color vacío accidentalmente
-                if self.active_buffer {
+        if index >= self.width * self.height {
+            return;
+        }
+
+        let samples = self.msaa.sample_count();
+        for sample_index in index * samples..(index + 1) * samples {
+            if self.zbuffer[sample_index] > depth {
+                self.zbuffer[sample_index] = depth;
+                if samples > 1 {
+                    self.sample_buffer[sample_index] = color;
+                } else if self.active_buffer {
                     self.buffer1[index] = color;
                 } else {
                     self.buffer2[index] = color;
                 }
-                self.zbuffer[index] = depth;
             }
         }
-    }   
+    }
 
     pub fn new(width: usize, height: usize) -> Self {
-        Framebuffer {
+        Framebuffer::with_msaa(width, height, Msaa::Off)
+    }
+
+    pub fn with_msaa(width: usize, height: usize, msaa: Msaa) -> Self {
+        let mut framebuffer = Framebuffer {
             width,
             height,
             buffer1: vec![0; width * height],
             buffer2: vec![0; width * height],
-            zbuffer: vec![f32::INFINITY; width * height],
+            zbuffer: Vec::new(),
+            sample_buffer: Vec::new(),
+            msaa,
             background_color: 0x000000,
             current_color: 0xFFFFFF,
             active_buffer: true,
-        }
+        };
+        framebuffer.set_msaa(msaa);
+        framebuffer
+    }
+
+    pub fn msaa(&self) -> Msaa {
+        self.msaa
+    }
+
+    /// Cambia el modo de MSAA; los buffers por muestra se vuelven a reservar
+    pub fn set_msaa(&mut self, msaa: Msaa) {
+        let pixels = self.width * self.height;
+        let samples = msaa.sample_count();
+
+        self.msaa = msaa;
+        self.zbuffer = vec![f32::INFINITY; pixels * samples];
+        self.sample_buffer = if samples > 1 { vec![self.background_color; pixels * samples] } else { Vec::new() };
     }
 
     pub fn clear(&mut self) {
@@ -83,9 +214,31 @@ impl Framebuffer {
         } else {
             self.buffer2.fill(self.background_color);
         }
+        self.sample_buffer.fill(self.background_color);
         self.zbuffer.fill(f32::INFINITY);
     }
 
+    /// Promedia las muestras de cada píxel en el buffer activo. Sin MSAA no hace nada,
+    /// porque el rasterizador ya escribe directo en el buffer activo.
+    pub fn resolve(&mut self) {
+        let samples = self.msaa.sample_count();
+        if samples == 1 {
+            return;
+        }
+
+        let target = if self.active_buffer { &mut self.buffer1 } else { &mut self.buffer2 };
+        target.par_iter_mut().zip(self.sample_buffer.par_chunks(samples)).for_each(|(pixel, pixel_samples)| {
+            let (mut r, mut g, mut b) = (0, 0, 0);
+            for &color in pixel_samples {
+                r += (color >> 16) & 0xFF;
+                g += (color >> 8) & 0xFF;
+                b += color & 0xFF;
+            }
+            let count = samples as u32;
+            *pixel = ((r / count) << 16) | ((g / count) << 8) | (b / count);
+        });
+    }
+
     pub fn get_active_buffer(&self) -> &[u32] {
         if self.active_buffer {
             &self.buffer1
@@ -94,6 +247,57 @@ impl Framebuffer {
         }
     }    
 
+    /// Divide el buffer de color y el zbuffer en tiles de tile_size x tile_size píxeles,
+    /// ordenados por filas (el tile (tx, ty) está en el índice ty * tiles_x + tx)
+    pub fn tiles_mut(&mut self, tile_size: usize) -> Vec<Tile<'_>> {
+        let tiles_x = self.width.div_ceil(tile_size);
+        let tiles_y = self.height.div_ceil(tile_size);
+        let samples = self.msaa.sample_count();
+
+        let mut tiles: Vec<Tile> = (0..tiles_y)
+            .flat_map(|ty| (0..tiles_x).map(move |tx| (tx, ty)))
+            .map(|(tx, ty)| {
+                let x = tx * tile_size;
+                let y = ty * tile_size;
+                let height = tile_size.min(self.height - y);
+                Tile {
+                    x,
+                    y,
+                    width: tile_size.min(self.width - x),
+                    height,
+                    msaa: self.msaa,
+                    samples,
+                    color_rows: Vec::with_capacity(height),
+                    depth_rows: Vec::with_capacity(height),
+                }
+            })
+            .collect();
+
+        // Con MSAA los tiles apuntan a las muestras; sin él, directo al buffer activo
+        let color = if samples > 1 {
+            &mut self.sample_buffer
+        } else if self.active_buffer {
+            &mut self.buffer1
+        } else {
+            &mut self.buffer2
+        };
+        let row_length = self.width * samples;
+        let segment_length = tile_size * samples;
+        let rows = color.chunks_mut(row_length).zip(self.zbuffer.chunks_mut(row_length));
+
+        for (y, (color_row, depth_row)) in rows.enumerate() {
+            let first_tile = (y / tile_size) * tiles_x;
+            let segments = color_row.chunks_mut(segment_length).zip(depth_row.chunks_mut(segment_length));
+
+            for (tile, (color_segment, depth_segment)) in tiles[first_tile..].iter_mut().zip(segments) {
+                tile.color_rows.push(color_segment);
+                tile.depth_rows.push(depth_segment);
+            }
+        }
+
+        tiles
+    }
+
     pub fn switch_buffers(&mut self) {
         self.active_buffer = !self.active_buffer;
     }
@@ -113,4 +317,86 @@ impl Framebuffer {
     pub fn get_current_color(&self) -> u32 {
         self.current_color
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nalgebra_glm::{Vec2, Vec3};
+    use crate::color::Color;
+    use crate::rasterizer::rasterize;
+    use crate::triangle::{CullMode, FrontFace, RasterState};
+    use crate::vertex::Vertex;
+
+    fn screen_vertex(x: f32, y: f32, color: Color) -> Vertex {
+        let mut vertex = Vertex::new(Vec3::new(x, y, 0.5), Vec3::z(), Vec2::zeros());
+        vertex.color = color;
+        vertex
+    }
+
+    // Rectángulo de pantalla de un solo color, como dos triángulos
+    fn rectangle(min_x: f32, max_x: f32, height: f32, color: Color) -> Vec<[Vertex; 3]> {
+        let corners = [(min_x, 0.0), (max_x, 0.0), (max_x, height), (min_x, height)].map(|(x, y)| screen_vertex(x, y, color));
+        vec![
+            [corners[0].clone(), corners[1].clone(), corners[2].clone()],
+            [corners[0].clone(), corners[2].clone(), corners[3].clone()],
+        ]
+    }
+
+    fn draw(msaa: Msaa, triangles: &[[Vertex; 3]]) -> Framebuffer {
+        let mut framebuffer = Framebuffer::with_msaa(4, 4, msaa);
+        framebuffer.clear();
+        let state = RasterState::new(CullMode::None, FrontFace::CounterClockwise);
+        rasterize(&mut framebuffer, triangles, &state, |fragment| Some(fragment.color.to_hex()));
+        framebuffer.resolve();
+        framebuffer
+    }
+
+    #[test]
+    fn edge_pixels_blend_only_with_msaa() {
+        // El borde izquierdo pasa por x = 1.4: el centro de la columna 1 queda adentro,
+        // pero solo dos de sus cuatro muestras (x = 1.625 y 1.875)
+        let white = rectangle(1.4, 4.0, 4.0, Color::new(255, 255, 255));
+
+        let binary = draw(Msaa::Off, &white);
+        for row in binary.get_active_buffer().chunks(4) {
+            assert_eq!(row, [0x000000, 0xFFFFFF, 0xFFFFFF, 0xFFFFFF]);
+        }
+
+        let blended = draw(Msaa::X4, &white);
+        for row in blended.get_active_buffer().chunks(4) {
+            assert_eq!(row, [0x000000, 0x7F7F7F, 0xFFFFFF, 0xFFFFFF]);
+        }
+    }
+
+    #[test]
+    fn covered_pixels_write_one_color_to_every_sample() {
+        // Un solo triángulo que tapa la pantalla, con colores distintos en cada vértice
+        let corners = [(-1.0, -1.0, Color::new(255, 0, 0)), (12.0, -1.0, Color::new(0, 255, 0)), (-1.0, 12.0, Color::new(0, 0, 255))];
+        let [v1, v2, v3] = corners.map(|(x, y, color)| screen_vertex(x, y, color));
+        let framebuffer = draw(Msaa::X4, &[[v1, v2, v3]]);
+
+        for (pixel, samples) in framebuffer.get_active_buffer().iter().zip(framebuffer.sample_buffer.chunks(4)) {
+            assert!(samples.iter().all(|&sample| sample == samples[0]), "{:06X?}", samples);
+            assert_eq!(*pixel, samples[0]);
+        }
+    }
+
+    #[test]
+    fn set_msaa_resizes_the_sample_buffers() {
+        let mut framebuffer = Framebuffer::new(8, 4);
+        assert_eq!(framebuffer.zbuffer.len(), 32);
+        assert!(framebuffer.sample_buffer.is_empty());
+
+        framebuffer.set_msaa(Msaa::X4);
+        assert_eq!(framebuffer.msaa(), Msaa::X4);
+        assert_eq!((framebuffer.zbuffer.len(), framebuffer.sample_buffer.len()), (128, 128));
+
+        framebuffer.set_msaa(Msaa::X8);
+        assert_eq!((framebuffer.zbuffer.len(), framebuffer.sample_buffer.len()), (256, 256));
+
+        framebuffer.set_msaa(Msaa::Off);
+        assert_eq!(framebuffer.zbuffer.len(), 32);
+        assert!(framebuffer.sample_buffer.is_empty());
+    }
+}
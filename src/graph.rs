@@ -0,0 +1,330 @@
+use nalgebra_glm::{Mat4, Vec3};
+use std::sync::Arc;
+use crate::maps::SurfaceMaps;
+use crate::params::ShaderParams;
+use crate::planet::Planet;
+use crate::ring::Ring;
+use crate::spaceship::Spaceship;
+use crate::texture::Texture;
+use crate::transform::create_model_matrix;
+use crate::triangle::RasterState;
+
+/// Índice de un nodo dentro de su [`SceneGraph`].
+pub type NodeId = usize;
+
+/// Lo que se dibuja en un nodo.
+pub enum Body {
+    Planet(Planet),
+    Ring(Ring),
+    Spaceship(Spaceship),
+}
+
+impl Body {
+    pub fn name(&self) -> &str {
+        match self {
+            Body::Planet(planet) => &planet.name,
+            Body::Ring(ring) => &ring.name,
+            Body::Spaceship(spaceship) => &spaceship.name,
+        }
+    }
+
+    /// Avanza `dt` segundos la órbita y el giro; la nave solo se mueve con el teclado (o
+    /// con la gravedad, que maneja `Scene`).
+    pub fn update(&mut self, dt: f32) {
+        if let Body::Planet(planet) = self {
+            planet.update_position(dt);
+            planet.update_rotation(dt);
+        }
+    }
+
+    /// Posición respecto al padre. Los hijos solo heredan esta traslación: una luna
+    /// sigue la órbita de su planeta pero no su giro diario ni su escala.
+    pub fn local_frame(&self) -> Mat4 {
+        let position = match self {
+            Body::Planet(planet) => planet.get_position(),
+            Body::Ring(_) => Vec3::zeros(),
+            Body::Spaceship(spaceship) => spaceship.position,
+        };
+        nalgebra_glm::translation(&position)
+    }
+
+    /// Giro, inclinación y escala del mesh dentro de su propio marco.
+    pub fn local_model(&self) -> Mat4 {
+        match self {
+            Body::Planet(planet) => create_model_matrix(Vec3::zeros(), planet.radius, planet.get_rotation()),
+            Body::Ring(ring) => ring.get_model_matrix(),
+            Body::Spaceship(spaceship) => create_model_matrix(Vec3::zeros(), spaceship.scale, spaceship.rotation),
+        }
+    }
+
+    pub fn shader(&self) -> &str {
+        match self {
+            Body::Planet(planet) => &planet.shader,
+            Body::Ring(ring) => &ring.shader,
+            Body::Spaceship(spaceship) => &spaceship.shader,
+        }
+    }
+
+    pub fn set_shader(&mut self, name: &str) {
+        match self {
+            Body::Planet(planet) => planet.shader = name.to_string(),
+            Body::Ring(ring) => ring.shader = name.to_string(),
+            Body::Spaceship(spaceship) => spaceship.shader = name.to_string(),
+        }
+    }
+
+    pub fn vertex_shader(&self) -> &str {
+        match self {
+            Body::Planet(planet) => &planet.vertex_shader,
+            Body::Ring(ring) => &ring.vertex_shader,
+            Body::Spaceship(spaceship) => &spaceship.vertex_shader,
+        }
+    }
+
+    pub fn shader_params(&self) -> &ShaderParams {
+        match self {
+            Body::Planet(planet) => &planet.shader_params,
+            Body::Ring(ring) => &ring.shader_params,
+            Body::Spaceship(spaceship) => &spaceship.shader_params,
+        }
+    }
+
+    pub fn shader_params_mut(&mut self) -> &mut ShaderParams {
+        match self {
+            Body::Planet(planet) => &mut planet.shader_params,
+            Body::Ring(ring) => &mut ring.shader_params,
+            Body::Spaceship(spaceship) => &mut spaceship.shader_params,
+        }
+    }
+
+    pub fn raster_state(&self) -> RasterState {
+        match self {
+            Body::Planet(planet) => planet.raster_state,
+            Body::Ring(ring) => ring.raster_state,
+            Body::Spaceship(spaceship) => spaceship.raster_state,
+        }
+    }
+}
+
+/// Cuerpo de la escena con su padre y el mesh que usa.
+pub struct Node {
+    pub parent: Option<NodeId>,
+    pub body: Body,
+    pub mesh: usize, // Índice en los meshes de la escena
+    pub texture: Option<Arc<Texture>>, // Para `Uniforms::texture`
+    pub maps: SurfaceMaps,             // Y `Uniforms::maps`
+    world_frame: Mat4, // Calculado en `SceneGraph::update_world_matrices`
+    previous: Option<(Mat4, Mat4)>, // Marco y modelo local antes del último paso
+}
+
+impl Node {
+    /// Marco en el mundo que heredan los hijos.
+    pub fn world_frame(&self) -> Mat4 {
+        self.world_frame
+    }
+
+    /// Matriz de modelo del mesh del nodo, para `Uniforms::model_matrix`.
+    pub fn world_model(&self) -> Mat4 {
+        self.world_frame * self.body.local_model()
+    }
+
+    pub fn world_position(&self) -> Vec3 {
+        self.world_frame.column(3).xyz()
+    }
+
+    /// Matriz de modelo entre el estado anterior al último paso (`alpha` = 0) y el
+    /// actual (1). La traslación se interpola exacta; el giro se interpola componente a
+    /// componente, lo que es suficiente para lo poco que gira un cuerpo en un paso.
+    pub fn interpolated_model(&self, alpha: f32) -> Mat4 {
+        let Some((previous_frame, previous_local)) = self.previous else {
+            return self.world_model();
+        };
+        let frame = previous_frame + (self.world_frame - previous_frame) * alpha;
+        let local = previous_local + (self.body.local_model() - previous_local) * alpha;
+        frame * local
+    }
+}
+
+/// Jerarquía de cuerpos: cada nodo se ubica respecto a su padre, así las lunas orbitan
+/// su planeta, los anillos siguen a Saturno y la nave puede acoplarse a un planeta.
+/// Los padres pueden estar en cualquier posición de la lista.
+#[derive(Default)]
+pub struct SceneGraph {
+    nodes: Vec<Node>,
+}
+
+impl SceneGraph {
+    pub fn new() -> Self {
+        SceneGraph::default()
+    }
+
+    /// Agrega un nodo y devuelve su índice. Las matrices de mundo se calculan en la
+    /// próxima llamada a [`SceneGraph::update_world_matrices`].
+    pub fn add(&mut self, parent: Option<NodeId>, body: Body, mesh: usize) -> NodeId {
+        if let Some(parent) = parent {
+            assert!(parent < self.nodes.len(), "El padre {} no existe", parent);
+        }
+        self.nodes.push(Node { parent, body, mesh, texture: None, maps: SurfaceMaps::default(), world_frame: Mat4::identity(), previous: None });
+        self.nodes.len() - 1
+    }
+
+    pub fn find(&self, name: &str) -> Option<NodeId> {
+        self.nodes.iter().position(|node| node.body.name() == name)
+    }
+
+    pub fn node(&self, id: NodeId) -> &Node {
+        &self.nodes[id]
+    }
+
+    pub fn node_mut(&mut self, id: NodeId) -> &mut Node {
+        &mut self.nodes[id]
+    }
+
+    /// Nodos en orden de dibujo.
+    pub fn iter(&self) -> impl Iterator<Item = (NodeId, &Node)> {
+        self.nodes.iter().enumerate()
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Avanza todos los cuerpos `dt` segundos y recalcula las matrices de mundo. El
+    /// estado anterior queda guardado para [`Node::interpolated_model`].
+    pub fn update(&mut self, dt: f32) {
+        self.save_previous();
+        self.update_bodies(dt);
+        self.update_world_matrices();
+    }
+
+    /// Guarda el estado actual como el anterior para [`Node::interpolated_model`]. Es la
+    /// primera mitad de `update`, para quien mueve los cuerpos por su cuenta antes de
+    /// recalcular las matrices.
+    pub fn save_previous(&mut self) {
+        self.update_world_matrices();
+        for node in &mut self.nodes {
+            node.previous = Some((node.world_frame, node.body.local_model()));
+        }
+    }
+
+    /// Avanza los cuerpos sin recalcular las matrices de mundo ni guardar el estado
+    /// anterior; para dar muchos pasos seguidos antes de un `update`.
+    pub fn update_bodies(&mut self, dt: f32) {
+        for node in &mut self.nodes {
+            node.body.update(dt);
+        }
+    }
+
+    /// Recalcula el marco de mundo de cada nodo a partir de los de sus ancestros.
+    /// Hay que llamarla después de mover un cuerpo a mano.
+    pub fn update_world_matrices(&mut self) {
+        let mut frames = vec![None; self.nodes.len()];
+        for id in 0..self.nodes.len() {
+            self.resolve_frame(id, &mut frames);
+        }
+        for (node, frame) in self.nodes.iter_mut().zip(frames) {
+            node.world_frame = frame.expect("marco sin calcular");
+        }
+    }
+
+    fn resolve_frame(&self, id: NodeId, frames: &mut [Option<Mat4>]) -> Mat4 {
+        if let Some(frame) = frames[id] {
+            return frame;
+        }
+        let node = &self.nodes[id];
+        let parent_frame = match node.parent {
+            Some(parent) => self.resolve_frame(parent, frames),
+            None => Mat4::identity(),
+        };
+        let frame = parent_frame * node.body.local_frame();
+        frames[id] = Some(frame);
+        frame
+    }
+
+    /// Cambia el padre de un nodo. La nave conserva su posición en el mundo; los demás
+    /// cuerpos conservan su órbita, ahora alrededor del nuevo padre.
+    ///
+    /// Entra en pánico si el nuevo padre es el propio nodo o uno de sus descendientes.
+    pub fn set_parent(&mut self, id: NodeId, parent: Option<NodeId>) {
+        let mut ancestor = parent;
+        while let Some(current) = ancestor {
+            assert!(current != id, "{} no puede ser su propio ancestro", self.nodes[id].body.name());
+            ancestor = self.nodes[current].parent;
+        }
+
+        self.update_world_matrices();
+        let parent_frame = parent.map_or(Mat4::identity(), |parent| self.nodes[parent].world_frame);
+        let world_position = self.nodes[id].world_position();
+
+        let node = &mut self.nodes[id];
+        if let Body::Spaceship(spaceship) = &mut node.body {
+            let inverse = parent_frame.try_inverse().unwrap_or(Mat4::identity());
+            spaceship.position = (inverse * world_position.push(1.0)).xyz();
+        }
+        node.parent = parent;
+        self.update_world_matrices();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: Vec3, expected: Vec3) {
+        assert!((actual - expected).norm() < 1e-4, "{:?} != {:?}", actual, expected);
+    }
+
+    #[test]
+    fn children_follow_their_parent() {
+        let mut graph = SceneGraph::new();
+        let earth = graph.add(None, Body::Planet(Planet::new("Tierra", 1.2, 5.0, 0.5, 0.3, 0x0077be, "moon")), 0);
+        let moon = graph.add(Some(earth), Body::Planet(Planet::new("Luna", 0.3, 1.0, 0.0, 0.0, 0xaaaaaa, "moon")), 0);
+
+        for _ in 0..3 {
+            graph.update(1.0);
+            let earth_position = graph.node(earth).world_position();
+            // La luna no orbita (velocidad 0), así que queda siempre a +X de la Tierra
+            assert_close(graph.node(moon).world_position(), earth_position + Vec3::x());
+        }
+        assert!(graph.node(earth).world_position().z.abs() > 1.0);
+
+        // La luna hereda la posición pero no la escala ni el giro del planeta
+        let model = graph.node(moon).world_model();
+        assert_close((model * Vec3::x().push(1.0)).xyz(), graph.node(moon).world_position() + Vec3::x() * 0.3);
+    }
+
+    #[test]
+    fn reparenting_keeps_the_spaceship_in_place() {
+        let mut graph = SceneGraph::new();
+        let planet = graph.add(None, Body::Planet(Planet::new("Marte", 0.8, 6.8, 0.1, 0.0, 0xd95d39, "cellular")), 0);
+        let ship = Spaceship::new("assets/models/ship.obj", Vec3::new(7.0, 1.0, 0.0), 0.05, Vec3::zeros(), "vertex_color");
+        let ship = graph.add(None, Body::Spaceship(ship), 1);
+        graph.update_world_matrices();
+
+        graph.set_parent(ship, Some(planet));
+        assert_close(graph.node(ship).world_position(), Vec3::new(7.0, 1.0, 0.0));
+
+        // Acoplada, la nave viaja con el planeta
+        let offset = graph.node(ship).world_position() - graph.node(planet).world_position();
+        graph.update(1.0);
+        assert_close(graph.node(ship).world_position() - graph.node(planet).world_position(), offset);
+
+        // Al soltarla queda donde estaba
+        let docked_position = graph.node(ship).world_position();
+        graph.set_parent(ship, None);
+        assert_close(graph.node(ship).world_position(), docked_position);
+    }
+
+    #[test]
+    #[should_panic(expected = "propio ancestro")]
+    fn cycles_are_rejected() {
+        let mut graph = SceneGraph::new();
+        let sun = graph.add(None, Body::Planet(Planet::new("Sol", 4.0, 0.0, 0.0, 0.0, 0xFFFF00, "sun")), 0);
+        let earth = graph.add(Some(sun), Body::Planet(Planet::new("Tierra", 1.2, 5.0, 0.0, 0.0, 0x0077be, "moon")), 0);
+        graph.set_parent(sun, Some(earth));
+    }
+}
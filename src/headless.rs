@@ -0,0 +1,99 @@
+use crate::framebuffer::{Framebuffer, Msaa};
+use crate::params::ShaderParams;
+use crate::scene::Scene;
+use crate::scene_file::SceneFile;
+use crate::shaders::ShaderRegistry;
+use std::io;
+
+/// Parámetros de un render sin ventana
+#[derive(Clone, Debug)]
+pub struct HeadlessOptions {
+    pub width: usize,
+    pub height: usize,
+    pub frames: usize,
+    pub seed: u64,
+    pub msaa: Msaa,
+    pub params: Vec<(String, ShaderParams)>, // Parámetros de shader por objeto
+    pub scene: SceneFile,
+}
+
+impl Default for HeadlessOptions {
+    fn default() -> Self {
+        HeadlessOptions {
+            width: 800,
+            height: 600,
+            frames: 1,
+            seed: 0,
+            msaa: Msaa::Off,
+            params: Vec::new(),
+            scene: SceneFile::default(),
+        }
+    }
+}
+
+/// Ejecuta la misma actualización y render que la ventana durante `options.frames`
+/// frames, sin abrir ninguna ventana. `on_frame` recibe cada frame ya resuelto.
+/// Para una misma semilla y cantidad de frames el resultado es idéntico entre corridas.
+/// Falla si la escena usa shaders o modelos que no existen. Devuelve el último frame y
+/// los objetos de `options.params` que no están en la escena, para que el llamador avise.
+pub fn render_frames<F>(options: &HeadlessOptions, mut on_frame: F) -> io::Result<(Framebuffer, Vec<String>)>
+where
+    F: FnMut(usize, &Framebuffer),
+{
+    let mut scene = Scene::from_file(&options.scene, ShaderRegistry::with_builtin(), options.width, options.height, options.seed)?;
+    let mut framebuffer = Framebuffer::with_msaa(options.width, options.height, options.msaa);
+    framebuffer.set_background_color(scene.background);
+    let unknown_objects = scene.apply_params(options.params.clone());
+
+    for frame in 0..options.frames {
+        if frame > 0 {
+            scene.update();
+        }
+
+        // Sin ventana no hace falta alternar buffers: el activo siempre tiene el último frame
+        framebuffer.clear();
+        scene.render(&mut framebuffer);
+        framebuffer.resolve();
+        on_frame(frame, &framebuffer);
+    }
+
+    Ok((framebuffer, unknown_objects))
+}
+
+/// Hash FNV-1a de los píxeles, para comparar frames entre corridas sin guardar imágenes
+pub fn frame_hash(pixels: &[u32]) -> u64 {
+    pixels.iter().fold(0xcbf29ce484222325, |hash, &pixel| {
+        pixel.to_le_bytes().iter().fold(hash, |hash, &byte| {
+            (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn last_frame_hash(options: &HeadlessOptions) -> u64 {
+        let (framebuffer, _) = render_frames(options, |_, _| {}).expect("La escena por defecto se renderiza");
+        frame_hash(framebuffer.get_active_buffer())
+    }
+
+    #[test]
+    fn unknown_objects_are_returned() {
+        let params = vec![("Tierra".to_string(), ShaderParams::new()), ("Vulcano".to_string(), ShaderParams::new())];
+        let options = HeadlessOptions { width: 16, height: 16, params, ..HeadlessOptions::default() };
+        let (_, unknown_objects) = render_frames(&options, |_, _| {}).unwrap();
+
+        assert_eq!(unknown_objects, ["Vulcano"]);
+    }
+
+    #[test]
+    fn runs_with_the_same_options_match() {
+        let options = HeadlessOptions { width: 80, height: 60, frames: 2, seed: 7, ..HeadlessOptions::default() };
+        let hash = last_frame_hash(&options);
+
+        assert_eq!(last_frame_hash(&options), hash);
+        // La semilla mueve las estrellas del fondo
+        assert_ne!(last_frame_hash(&HeadlessOptions { seed: 8, ..options }), hash);
+    }
+}
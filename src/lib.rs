@@ -0,0 +1,53 @@
+//! Rasterizador por software con el que se dibuja el sistema solar.
+//!
+//! El pipeline completo está en [`render()`]: recibe los vértices de un modelo
+//! (ver [`obj::Obj`]), los transforma con los [`Uniforms`] del objeto, recorta,
+//! rasteriza por tiles en paralelo y sombrea cada píxel en un [`Framebuffer`].
+//! [`scene::Scene`] arma una escena sobre esas piezas a partir de un archivo
+//! ([`scene_file`]) y [`headless`] la renderiza sin ventana.
+//!
+//! ```no_run
+//! use proyecto3_gc::framebuffer::Framebuffer;
+//! use proyecto3_gc::scene::Scene;
+//!
+//! let mut framebuffer = Framebuffer::new(800, 600);
+//! let scene = Scene::solar_system(800, 600, 0);
+//!
+//! framebuffer.clear();
+//! scene.render(&mut framebuffer);
+//! framebuffer.resolve();
+//! ```
+
+pub mod camera;
+pub mod clipping;
+pub mod clock;
+pub mod color;
+pub mod export;
+pub mod fragment;
+pub mod framebuffer;
+pub mod graph;
+pub mod headless;
+pub mod light;
+pub mod maps;
+pub mod material;
+pub mod nbody;
+pub mod noise;
+pub mod obj;
+pub mod orbit;
+pub mod params;
+pub mod planet;
+pub mod rasterizer;
+pub mod render;
+pub mod ring;
+pub mod scene;
+pub mod scene_file;
+pub mod shaders;
+pub mod shadow;
+pub mod spaceship;
+pub mod texture;
+pub mod transform;
+pub mod triangle;
+pub mod vertex;
+
+pub use framebuffer::Framebuffer;
+pub use render::{render, Uniforms};
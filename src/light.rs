@@ -0,0 +1,126 @@
+use nalgebra_glm::Vec3;
+use crate::color::Color;
+use crate::shadow::ShadowMap;
+
+/// Fuente de luz para el sombreado por fragmento. Las posiciones y direcciones están en
+/// el espacio del mundo.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Light {
+    /// Emite en todas las direcciones desde `position`, como el Sol. No se atenúa con la
+    /// distancia: a la escala de la escena los planetas exteriores quedarían a oscuras.
+    Point { position: Vec3, color: Color, intensity: f32 },
+    /// Rayos paralelos que viajan en `direction`, como los de una fuente muy lejana.
+    Directional { direction: Vec3, color: Color, intensity: f32 },
+    /// Llega igual a toda superficie, para que el lado nocturno no quede negro.
+    Ambient { color: Color, intensity: f32 },
+}
+
+/// Cómo refleja la luz una superficie. La parte difusa toma el color que calcula el
+/// shader y el brillo especular, el color de la luz.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Surface {
+    pub specular: f32,  // Fracción de la luz que se refleja como brillo
+    pub shininess: f32, // Exponente de Blinn-Phong: más alto, brillo más chico
+}
+
+impl Surface {
+    /// Sin brillo especular, solo Lambert.
+    pub const MATTE: Surface = Surface::new(0.0, 1.0);
+
+    pub const fn new(specular: f32, shininess: f32) -> Self {
+        Surface { specular, shininess }
+    }
+}
+
+/// Color de un punto en `position` con normal `normal` iluminado por `lights` y visto
+/// desde `eye`: difusa de Lambert más especular de Blinn-Phong. Si hay `shadow`, la luz
+/// puntual ubicada donde se dibujó el mapa llega solo a lo que ella ve.
+pub fn shade(
+    lights: &[Light],
+    albedo: Color,
+    position: Vec3,
+    normal: Vec3,
+    eye: Vec3,
+    surface: Surface,
+    shadow: Option<&ShadowMap>,
+) -> Color {
+    let normal = normal.normalize();
+    let view = (eye - position).normalize();
+    let mut diffuse = Vec3::zeros();
+    let mut specular = Vec3::zeros();
+
+    for light in lights {
+        let (to_light, color, intensity) = match *light {
+            Light::Point { position: light_position, color, intensity } => {
+                let intensity = match shadow {
+                    Some(shadow) if shadow.light_position == light_position => intensity * shadow.visibility(position, normal),
+                    _ => intensity,
+                };
+                ((light_position - position).normalize(), color, intensity)
+            }
+            Light::Directional { direction, color, intensity } => (-direction.normalize(), color, intensity),
+            Light::Ambient { color, intensity } => {
+                diffuse += color.to_vec3() * intensity;
+                continue;
+            }
+        };
+
+        let lambert = normal.dot(&to_light);
+        if lambert <= 0.0 || intensity <= 0.0 {
+            continue;
+        }
+        let radiance = color.to_vec3() * intensity;
+        diffuse += radiance * lambert;
+
+        if surface.specular > 0.0 {
+            let half = (to_light + view).normalize();
+            specular += radiance * surface.specular * normal.dot(&half).max(0.0).powf(surface.shininess);
+        }
+    }
+
+    Color::from_vec3(albedo.to_vec3().component_mul(&diffuse) + specular)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WHITE: Color = Color::new(255, 255, 255);
+
+    fn sun() -> Light {
+        Light::Point { position: Vec3::new(10.0, 0.0, 0.0), color: WHITE, intensity: 1.0 }
+    }
+
+    #[test]
+    fn night_side_only_gets_ambient_light() {
+        let lights = [sun(), Light::Ambient { color: WHITE, intensity: 0.1 }];
+        let albedo = Color::new(200, 100, 50);
+        let eye = Vec3::new(0.0, 0.0, 10.0);
+
+        let day = shade(&lights, albedo, Vec3::x(), Vec3::x(), eye, Surface::MATTE, None);
+        let night = shade(&lights, albedo, -Vec3::x(), -Vec3::x(), eye, Surface::MATTE, None);
+        // El terminador: de frente a la cámara, pero de costado respecto al Sol
+        let terminator = shade(&lights, albedo, Vec3::z(), Vec3::z(), eye, Surface::MATTE, None);
+
+        assert_eq!(day, Color::new(220, 110, 55));
+        assert_eq!(night, Color::new(20, 10, 5));
+        assert_eq!(terminator, night);
+    }
+
+    #[test]
+    fn specular_highlight_follows_the_half_vector() {
+        let lights = [Light::Directional { direction: Vec3::new(0.0, 0.0, -1.0), color: WHITE, intensity: 0.5 }];
+        let shiny = Surface::new(0.5, 32.0);
+        let albedo = Color::new(0, 0, 100);
+        let eye = Vec3::new(0.0, 0.0, 10.0);
+
+        // Luz y cámara del mismo lado: el brillo está donde la normal mira a ambas
+        let facing = shade(&lights, albedo, Vec3::zeros(), Vec3::z(), eye, shiny, None);
+        let tilted = shade(&lights, albedo, Vec3::zeros(), Vec3::new(0.0, 0.5, 1.0), eye, shiny, None);
+        let matte = shade(&lights, albedo, Vec3::zeros(), Vec3::z(), eye, Surface::MATTE, None);
+
+        assert_eq!(matte, Color::new(0, 0, 50));
+        assert_eq!(facing, Color::new(64, 64, 114));
+        assert!(tilted.to_vec3().x < 0.05);
+    }
+}
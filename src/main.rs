@@ -1,201 +1,236 @@
-use nalgebra_glm::{Vec3, Mat4, look_at, perspective};
-use minifb::{Key, Window, WindowOptions};
-use std::time::Duration;
-use std::f32::consts::PI;
-use rand::Rng;
-
-mod framebuffer;
-mod triangle;
-mod vertex;
-mod obj;
-mod color;
-mod fragment;
-mod shaders;
-mod camera;
-mod planet;
-use rayon::prelude::*;
-
-use framebuffer::Framebuffer;
-use vertex::Vertex;
-use obj::Obj;
-use camera::Camera;
-use triangle::triangle;
-use shaders::{vertex_shader, fragment_shader};
-use fastnoise_lite::{FastNoiseLite, NoiseType};
-use planet::Planet;
-
-pub struct Uniforms {
-    model_matrix: Mat4,
-    view_matrix: Mat4,
-    projection_matrix: Mat4,
-    viewport_matrix: Mat4,
-    time: u32,
-    noise: FastNoiseLite,
-}
+use nalgebra_glm::Vec3;
+use minifb::{Key, KeyRepeat, Window, WindowOptions};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use proyecto3_gc::export::{save_screenshot, FrameRecorder, ImageFormat};
+use proyecto3_gc::framebuffer::{Framebuffer, Msaa};
+use proyecto3_gc::headless::{frame_hash, render_frames, HeadlessOptions};
+use proyecto3_gc::nbody::Integrator;
+use proyecto3_gc::params::{load_params, save_params};
+use proyecto3_gc::rasterizer::RenderStats;
+use proyecto3_gc::scene::{ParamEditor, Scene};
+use proyecto3_gc::scene_file::{load_scene, SceneFile};
+use proyecto3_gc::shaders::ShaderRegistry;
+use proyecto3_gc::triangle::Interpolation;
+
+// Reservas de memoria del programa, para que el benchmark las mida en lugar de estimarlas.
+// Contar cuesta dos operaciones atómicas por reserva, así que solo se instala con la
+// feature `bench-alloc`
+#[cfg(feature = "bench-alloc")]
+mod allocations {
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    struct CountingAllocator;
+
+    static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+    static ALLOCATED_BYTES: AtomicU64 = AtomicU64::new(0);
+
+    unsafe impl GlobalAlloc for CountingAllocator {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+            ALLOCATED_BYTES.fetch_add(layout.size() as u64, Ordering::Relaxed);
+            unsafe { System.alloc(layout) }
+        }
 
-pub struct Spaceship {
-    pub position: Vec3,
-    pub scale: f32,
-    pub rotation: Vec3,
-    pub model: Obj, // El modelo .obj cargado
-    pub shader_index: u32, // Shader que usará la nave
-}
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            unsafe { System.dealloc(ptr, layout) }
+        }
 
-impl Spaceship {
-    pub fn new(model_path: &str, position: Vec3, scale: f32, rotation: Vec3, shader_index: u32) -> Self {
-        Spaceship {
-            position,
-            scale,
-            rotation,
-            model: Obj::load("assets/models/ship.obj").expect("Failed to load spaceship model"),
-            shader_index,
+        unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+            ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+            ALLOCATED_BYTES.fetch_add(new_size as u64, Ordering::Relaxed);
+            unsafe { System.realloc(ptr, layout, new_size) }
         }
     }
 
-    pub fn update_position(&mut self, direction: Vec3) {
-        self.position += direction;
-    }
+    #[global_allocator]
+    static ALLOCATOR: CountingAllocator = CountingAllocator;
 
-    pub fn get_model_matrix(&self) -> Mat4 {
-        create_model_matrix(self.position, self.scale, self.rotation)
+    // Reservas y bytes reservados desde el inicio del programa
+    pub fn counts() -> Option<(u64, u64)> {
+        Some((ALLOCATIONS.load(Ordering::Relaxed), ALLOCATED_BYTES.load(Ordering::Relaxed)))
     }
 }
 
-fn create_noise() -> FastNoiseLite {
-    let mut noise = FastNoiseLite::with_seed(1337);
-    noise.set_noise_type(Some(NoiseType::OpenSimplex2));
-    noise
+#[cfg(not(feature = "bench-alloc"))]
+mod allocations {
+    pub fn counts() -> Option<(u64, u64)> {
+        None
+    }
 }
 
-fn create_model_matrix(translation: Vec3, scale: f32, rotation: Vec3) -> Mat4 {
-    let (sin_x, cos_x) = rotation.x.sin_cos();
-    let (sin_y, cos_y) = rotation.y.sin_cos();
-    let (sin_z, cos_z) = rotation.z.sin_cos();
-
-    let rotation_matrix_x = Mat4::new(
-        1.0, 0.0, 0.0, 0.0,
-        0.0, cos_x, -sin_x, 0.0,
-        0.0, sin_x, cos_x, 0.0,
-        0.0, 0.0, 0.0, 1.0,
-    );
-
-    let rotation_matrix_y = Mat4::new(
-        cos_y, 0.0, sin_y, 0.0,
-        0.0, 1.0, 0.0, 0.0,
-        -sin_y, 0.0, cos_y, 0.0,
-        0.0, 0.0, 0.0, 1.0,
-    );
-
-    let rotation_matrix_z = Mat4::new(
-        cos_z, -sin_z, 0.0, 0.0,
-        sin_z, cos_z, 0.0, 0.0,
-        0.0, 0.0, 1.0, 0.0,
-        0.0, 0.0, 0.0, 1.0,
-    );
-
-    let rotation_matrix = rotation_matrix_z * rotation_matrix_y * rotation_matrix_x;
+fn run_benchmark(scene_file: &SceneFile, frames: usize, msaa: Msaa, seed: u64) {
+    let (width, height) = (800, 600);
+    let mut scene = build_scene(scene_file, width, height, seed);
+    let mut framebuffer = Framebuffer::with_msaa(width, height, msaa);
+    framebuffer.set_background_color(scene.background);
+    let mut total = RenderStats::default();
 
-    let transform_matrix = Mat4::new(
-        scale, 0.0, 0.0, translation.x,
-        0.0, scale, 0.0, translation.y,
-        0.0, 0.0, scale, translation.z,
-        0.0, 0.0, 0.0, 1.0,
+    let allocations_before = allocations::counts();
+    let start = Instant::now();
+    for _ in 0..frames {
+        framebuffer.clear();
+        total += scene.render(&mut framebuffer);
+        framebuffer.resolve();
+        framebuffer.switch_buffers();
+        scene.update();
+    }
+    let elapsed = start.elapsed();
+    let allocations_after = allocations::counts();
+
+    let per_frame = |value: u64| value as f64 / frames as f64;
+
+    println!("Benchmark: {} frames a {}x{}, MSAA {:?}", frames, width, height, msaa);
+    println!("  Tiempo por frame:               {:.2} ms", elapsed.as_secs_f64() * 1000.0 / frames as f64);
+    println!("  Triángulos rasterizados:        {:.0}", per_frame(total.triangles));
+    println!("  Píxeles cubiertos:              {:.0}", per_frame(total.covered));
+    println!("  Descartados por z temprana:     {:.0}", per_frame(total.depth_rejected));
+    println!(
+        "  Fragmentos sombreados:          {:.0} ({:.1}% de los cubiertos)",
+        per_frame(total.shaded),
+        100.0 * total.shaded as f64 / total.covered.max(1) as f64,
     );
-
-    transform_matrix * rotation_matrix
+    // Medidas con el asignador del programa: los fragmentos ya no se guardan en memoria,
+    // así que no crecen con los píxeles cubiertos
+    match allocations_before.zip(allocations_after) {
+        Some(((allocations, bytes), (allocations_after, bytes_after))) => println!(
+            "  Reservas de memoria por frame:  {:.0} ({:.1} KiB)",
+            per_frame(allocations_after - allocations),
+            per_frame(bytes_after - bytes) / 1024.0,
+        ),
+        None => println!("  Reservas de memoria por frame:  sin medir (compilar con --features bench-alloc)"),
+    }
 }
 
-fn create_view_matrix(eye: Vec3, center: Vec3, up: Vec3) -> Mat4 {
-    look_at(&eye, &center, &up)
+// Los errores de la escena ya traen el archivo y la línea; no hace falta un backtrace
+fn build_scene(scene_file: &SceneFile, width: usize, height: usize, seed: u64) -> Scene {
+    Scene::from_file(scene_file, ShaderRegistry::with_builtin(), width, height, seed).unwrap_or_else(|error| {
+        eprintln!("{}", error);
+        std::process::exit(1);
+    })
 }
 
-fn create_perspective_matrix(window_width: f32, window_height: f32) -> Mat4 {
-    let fov = 60.0 * PI / 180.0;
-    let aspect_ratio = window_width / window_height;
-    let near = 0.1;
-    let far = 1000.0;
-
-    perspective(fov, aspect_ratio, near, far)
+// Valor que sigue a una bandera de la línea de comandos, por ejemplo `--msaa 4`
+fn arg_value<'a>(args: &'a [String], flag: &str) -> Option<&'a str> {
+    let position = args.iter().position(|arg| arg == flag)?;
+    args.get(position + 1).map(|value| value.as_str())
 }
 
-fn create_viewport_matrix(width: f32, height: f32) -> Mat4 {
-    Mat4::new(
-        width / 2.0, 0.0, 0.0, width / 2.0,
-        0.0, -height / 2.0, 0.0, height / 2.0,
-        0.0, 0.0, 1.0, 0.0,
-        0.0, 0.0, 0.0, 1.0,
-    )
+// Interpreta un tamaño de la forma `ANCHOxALTO`
+fn parse_size(size: &str) -> Option<(usize, usize)> {
+    let (width, height) = size.split_once('x')?;
+    Some((width.parse().ok()?, height.parse().ok()?))
 }
 
-fn render(
-    framebuffer: &mut Framebuffer,
-    uniforms: &Uniforms,
-    vertex_array: &[Vertex],
-    current_shader: u32,
-    framebuffer_width: usize,
-    framebuffer_height: usize,
-) {
-    let transformed_vertices: Vec<_> = vertex_array.par_iter()
-        .map(|vertex| vertex_shader(vertex, uniforms))
-        .collect();
-
-    let triangles: Vec<_> = transformed_vertices.par_chunks(3)
-        .filter_map(|chunk| {
-            if chunk.len() == 3 {
-                Some([chunk[0].clone(), chunk[1].clone(), chunk[2].clone()])
-            } else {
-                None
-            }
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+
+    // --msaa 2|4|8 activa el antialiasing por multimuestreo desde el inicio
+    let msaa = match arg_value(&args, "--msaa") {
+        Some(value) => value
+            .parse()
+            .ok()
+            .and_then(Msaa::from_samples)
+            .expect("--msaa acepta 1, 2, 4 u 8 muestras"),
+        None => Msaa::Off,
+    };
+
+    // --seed fija la semilla de la escena (las estrellas de fondo)
+    let seed = arg_value(&args, "--seed").map(|value| value.parse().expect("--seed espera un número")).unwrap_or(0);
+
+    // --format png|ppm elige el formato de capturas y secuencias; --depth agrega el zbuffer
+    let format = arg_value(&args, "--format")
+        .map(|value| ImageFormat::from_name(value).expect("--format acepta png o ppm"))
+        .unwrap_or(ImageFormat::Png);
+    let with_depth = args.iter().any(|arg| arg == "--depth");
+
+    // --params FILE carga los parámetros de shader por objeto; sin la bandera se usa
+    // shader_params.toml si existe. F5 guarda ahí los ajustes hechos con el teclado.
+    let params_path = PathBuf::from(arg_value(&args, "--params").unwrap_or("shader_params.toml"));
+    let params = if params_path.exists() || args.iter().any(|arg| arg == "--params") {
+        load_params(&params_path).unwrap_or_else(|error| {
+            eprintln!("No se pudieron leer los parámetros de {}: {}", params_path.display(), error);
+            std::process::exit(1);
         })
-        .collect();
-
-    for tri in &triangles {
-        let fragments = triangle(&tri[0], &tri[1], &tri[2]);
-
-        for fragment in fragments {
-            let x = fragment.position.x as usize;
-            let y = fragment.position.y as usize;
-
-            if x < framebuffer_width && y < framebuffer_height {
-                let index = y * framebuffer_width + x;
-                framebuffer.set_color_at_index(
-                    index,
-                    fragment_shader(&fragment, uniforms, current_shader).to_hex(),
-                    fragment.depth,
-                );
-            }
+    } else {
+        Vec::new()
+    };
+
+    // --scene FILE lee la escena de un archivo; sin la bandera se usa el sistema solar
+    // de assets/scenes/solar_system.toml
+    let mut scene_file = match arg_value(&args, "--scene") {
+        Some(path) => load_scene(Path::new(path)).unwrap_or_else(|error| {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        }),
+        None => SceneFile::default(),
+    };
+
+    // --nbody [leapfrog|verlet] arranca en el modo de N cuerpos, con el integrador de la
+    // escena si no se indica otro
+    if args.iter().any(|arg| arg == "--nbody") {
+        scene_file.physics.enabled = true;
+        if let Some(name) = arg_value(&args, "--nbody").filter(|value| !value.starts_with("--")) {
+            scene_file.physics.integrator = Integrator::from_name(name).expect("--nbody acepta leapfrog o verlet");
         }
     }
-}
 
-fn generate_stars(count: usize, width: usize, height: usize) -> Vec<(usize, usize)> {
-    let mut rng = rand::thread_rng();
-    (0..count)
-        .map(|_| {
-            (
-                rng.gen_range(0..width),
-                rng.gen_range(0..height),
-            )
-        })
-        .collect()
-}
+    // --record DIR guarda cada frame numerado en DIR, para armar videos
+    let mut recorder = arg_value(&args, "--record").map(|directory| {
+        FrameRecorder::new(Path::new(directory), format, with_depth).expect("No se pudo crear el directorio de grabación")
+    });
 
-fn draw_stars(framebuffer: &mut Framebuffer, stars: &[(usize, usize)]) {
-    framebuffer.set_current_color(0xFFFFFF); // Blanco para las estrellas
-    for &(x, y) in stars {
-        let index = y * framebuffer.width + x;
-        framebuffer.set_color_at_index(index, 0xFFFFFF, 1.0);
+    // --bench [frames] renderiza la escena sin ventana y reporta el trabajo del rasterizador
+    if args.iter().any(|arg| arg == "--bench") {
+        let frames = arg_value(&args, "--bench").and_then(|value| value.parse().ok()).unwrap_or(300);
+        run_benchmark(&scene_file, frames, msaa, seed);
+        return;
+    }
+
+    // --headless renderiza --frames N frames sin abrir ventana (por ejemplo en CI) y
+    // muestra un hash del último frame, estable para la misma semilla y cantidad de frames
+    if args.iter().any(|arg| arg == "--headless") {
+        let (width, height) = match arg_value(&args, "--size") {
+            Some(size) => parse_size(size).expect("--size espera un tamaño como 1920x1080"),
+            None => (800, 600),
+        };
+        let options = HeadlessOptions {
+            width,
+            height,
+            frames: arg_value(&args, "--frames").map(|value| value.parse().expect("--frames espera un número")).unwrap_or(1),
+            seed,
+            msaa,
+            params,
+            scene: scene_file,
+        };
+
+        let (framebuffer, unknown_objects) = render_frames(&options, |_, framebuffer| {
+            if let Some(recorder) = recorder.as_mut() {
+                recorder.record(framebuffer).expect("No se pudo guardar el frame");
+            }
+        })
+        .unwrap_or_else(|error| {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        });
+        for object in unknown_objects {
+            eprintln!("Parámetros para un objeto que no existe: {}", object);
+        }
+        println!("{:016x}", frame_hash(framebuffer.get_active_buffer()));
+        return;
     }
-}
 
-fn main() {
     let window_width = 800;
     let window_height = 600;
     let framebuffer_width = 800;
     let framebuffer_height = 600;
     let frame_delay = Duration::from_millis(16);
 
-    let mut framebuffer = Framebuffer::new(framebuffer_width, framebuffer_height);
+    let mut scene = build_scene(&scene_file, framebuffer_width, framebuffer_height, seed);
+    let mut framebuffer = Framebuffer::with_msaa(framebuffer_width, framebuffer_height, msaa);
+    framebuffer.set_background_color(scene.background);
     let mut window = Window::new(
         "Sistema Solar con Nave Espacial",
         window_width,
@@ -203,87 +238,33 @@ fn main() {
         WindowOptions::default(),
     ).unwrap();
 
-    let mut camera = Camera::new(
-        Vec3::new(0.0, 10.0, 30.0),  // Eleva la cámara en el eje Y
-        Vec3::new(0.0, 0.0, 0.0),    // Sigue apuntando al centro
-        Vec3::new(0.0, 1.0, 0.0),    // Mantén el eje "arriba"
-    );    
-
-    let stars = generate_stars(500, framebuffer_width, framebuffer_height);
-
-    let mut planets = vec![
-        Planet::new("Sol", 4.0, 0.0, 0.0, 0.0, 0xFFFF00, 0),
-        Planet::new("Mercurio", 0.5, 2.0, 0.04, 0.1, 0xffc300, 1),
-        Planet::new("Venus", 1.0, 3.5, 0.03, 0.08, 0xe24e42, 2),
-        Planet::new("Tierra", 1.2, 5.0, 0.02, 0.07, 0x0077be, 3),
-        Planet::new("Marte", 0.8, 6.8, 0.01, 0.05, 0xd95d39, 4),
-        Planet::new("Júpiter", 4.0, 12.0, 0.005, 0.03, 0xfff9a6, 5),
-        Planet::new("Saturno", 3.5, 16.0, 0.004, 0.02, 0xc49c48, 6),
-    ];
-
-    let mut spaceship = Spaceship::new(
-        "assets/models/ship.obj", // Ruta de tu modelo de nave
-        Vec3::new(5.5, 1.5, 0.0),      // Cerca de la Tierra, en su órbita
-        0.05,                           // Escala pequeña
-        Vec3::new(0.0, 0.0, 0.0),      // Rotación inicial
-        7,                             // Shader para la nave
-    );
-
-    let rotation = Vec3::new(0.0, 0.0, 0.0);
-    let mut time = 0;
-    let planet_obj = Obj::load("assets/models/sphere.obj").expect("Failed to load obj");
-    let mut current_shader = 0;
+    for object in scene.apply_params(params) {
+        eprintln!("Parámetros para un objeto que no existe: {}", object);
+    }
+    let mut editor = ParamEditor::default();
 
+    let mut last_frame = Instant::now();
     while window.is_open() {
+        // La simulación avanza según el tiempo real transcurrido, no por frame
+        let now = Instant::now();
+        scene.advance((now - last_frame).as_secs_f64());
+        last_frame = now;
+
         framebuffer.clear();
-        draw_stars(&mut framebuffer, &stars);
-
-        let view_matrix = create_view_matrix(camera.eye, camera.center, camera.up);
-        let projection_matrix = create_perspective_matrix(window_width as f32, window_height as f32);
-        let viewport_matrix = create_viewport_matrix(framebuffer_width as f32, framebuffer_height as f32);
-
-        // Renderizar los planetas
-        for planet in &mut planets {
-            planet.update_position();
-            let model_matrix = create_model_matrix(planet.get_position(), planet.radius, rotation);
-
-            let uniforms = Uniforms {
-                model_matrix,
-                view_matrix,
-                projection_matrix,
-                viewport_matrix,
-                time,
-                noise: create_noise(),
-            };
+        scene.render(&mut framebuffer);
+        framebuffer.resolve();
 
-            render(
-                &mut framebuffer,
-                &uniforms,
-                &planet_obj.get_vertex_array(),
-                planet.shader_index,
-                framebuffer_width,
-                framebuffer_height,
-            );
+        if let Some(recorder) = recorder.as_mut() {
+            recorder.record(&framebuffer).expect("No se pudo guardar el frame");
         }
 
-        // Renderizar la nave espacial
-        let spaceship_uniforms = Uniforms {
-            model_matrix: spaceship.get_model_matrix(),
-            view_matrix,
-            projection_matrix,
-            viewport_matrix,
-            time,
-            noise: create_noise(),
-        };
-
-        render(
-            &mut framebuffer,
-            &spaceship_uniforms,
-            &spaceship.model.get_vertex_array(),
-            spaceship.shader_index,
-            framebuffer_width,
-            framebuffer_height,
-        );
+        // P guarda una captura del frame actual en screenshots/
+        if window.is_key_pressed(Key::P, KeyRepeat::No) {
+            match save_screenshot(&framebuffer, Path::new("screenshots"), format, with_depth) {
+                Ok(path) => println!("Captura guardada en {}", path.display()),
+                Err(error) => eprintln!("No se pudo guardar la captura: {}", error),
+            }
+        }
 
         // Actualizar el buffer
         window
@@ -291,21 +272,123 @@ fn main() {
             .unwrap();
 
         framebuffer.switch_buffers();
-        time += 1;
         std::thread::sleep(frame_delay);
 
+        // Espacio pausa, R invierte el tiempo y . , cambian la escala (1x a 10000x)
+        if window.is_key_pressed(Key::Space, KeyRepeat::No) {
+            scene.clock.toggle_pause();
+            println!("{}", if scene.clock.paused { "Pausa" } else { "En marcha" });
+        }
+        if window.is_key_pressed(Key::R, KeyRepeat::No) {
+            scene.clock.toggle_reverse();
+            println!("Tiempo {}", if scene.clock.reversed { "hacia atrás" } else { "hacia adelante" });
+        }
+        if window.is_key_pressed(Key::Period, KeyRepeat::No) {
+            scene.clock.speed_up();
+            println!("Escala de tiempo: {}x", scene.clock.scale);
+        }
+        if window.is_key_pressed(Key::Comma, KeyRepeat::No) {
+            scene.clock.slow_down();
+            println!("Escala de tiempo: {}x", scene.clock.scale);
+        }
+
+        // I alterna entre interpolación con corrección de perspectiva y afín para comparar
+        if window.is_key_pressed(Key::I, KeyRepeat::No) {
+            scene.interpolation = match scene.interpolation {
+                Interpolation::PerspectiveCorrect => Interpolation::Affine,
+                Interpolation::Affine => Interpolation::PerspectiveCorrect,
+            };
+        }
+
+        // M recorre los modos de MSAA (sin AA, 2x, 4x, 8x) para compararlos
+        if window.is_key_pressed(Key::M, KeyRepeat::No) {
+            let msaa = framebuffer.msaa().next();
+            framebuffer.set_msaa(msaa);
+            println!("MSAA: {:?}", msaa);
+        }
+
+        // H activa o desactiva las sombras, que cuestan un render de la escena desde el Sol
+        if window.is_key_pressed(Key::H, KeyRepeat::No) {
+            scene.shadows.enabled = !scene.shadows.enabled;
+            println!("Sombras: {}", if scene.shadows.enabled { "activadas" } else { "desactivadas" });
+        }
+
+        // S recorre los fragment shaders registrados sobre la nave
+        if window.is_key_pressed(Key::S, KeyRepeat::No) {
+            if let Some(next) = scene.shaders.next_fragment(&scene.spaceship().shader) {
+                let next = next.to_string();
+                println!("Shader de la nave: {}", next);
+                scene.spaceship_mut().shader = next;
+            }
+        }
+
+        // D acopla la nave al planeta más cercano o la suelta
+        if window.is_key_pressed(Key::D, KeyRepeat::No) {
+            match scene.toggle_dock() {
+                Some(planet) => println!("Nave acoplada a {}", planet),
+                None if scene.nbody().is_some() => println!("La nave no se acopla en el modo de N cuerpos"),
+                None => println!("Nave suelta"),
+            }
+        }
+
+        // N activa o desactiva la gravedad mutua, V cambia el integrador y G muestra la
+        // energía y los momentos del sistema
+        if window.is_key_pressed(Key::N, KeyRepeat::No) {
+            if scene.nbody().is_some() {
+                scene.disable_nbody();
+                println!("Órbitas guionadas");
+            } else {
+                scene.enable_nbody();
+                println!("Modo de N cuerpos ({}): las flechas empujan la nave", scene.physics.integrator.name());
+            }
+        }
+        if window.is_key_pressed(Key::V, KeyRepeat::No) {
+            scene.set_integrator(scene.physics.integrator.next());
+            println!("Integrador: {}", scene.physics.integrator.name());
+        }
+        if window.is_key_pressed(Key::G, KeyRepeat::No) {
+            match scene.nbody() {
+                Some(system) => println!("t = {:.1} s: {}", scene.clock.time, system.diagnostics()),
+                None => println!("La gravedad está desactivada (tecla N)"),
+            }
+        }
+
+        // Tab elige el objeto, [ y ] el parámetro de su shader, + y - lo ajustan
+        if window.is_key_pressed(Key::Tab, KeyRepeat::No) {
+            println!("{}", editor.next_object(&scene));
+        }
+        if window.is_key_pressed(Key::LeftBracket, KeyRepeat::No) {
+            println!("{}", editor.next_param(&scene, -1));
+        }
+        if window.is_key_pressed(Key::RightBracket, KeyRepeat::No) {
+            println!("{}", editor.next_param(&scene, 1));
+        }
+        if window.is_key_pressed(Key::Equal, KeyRepeat::Yes) {
+            println!("{}", editor.adjust(&mut scene, 1));
+        }
+        if window.is_key_pressed(Key::Minus, KeyRepeat::Yes) {
+            println!("{}", editor.adjust(&mut scene, -1));
+        }
+        if window.is_key_pressed(Key::F5, KeyRepeat::No) {
+            let objects = scene.objects().map(|(object, _, params)| (object, params));
+            match save_params(&params_path, objects) {
+                Ok(()) => println!("Parámetros guardados en {}", params_path.display()),
+                Err(error) => eprintln!("No se pudieron guardar los parámetros: {}", error),
+            }
+        }
+
         // Opcional: Control de la nave con teclas
         if window.is_key_down(Key::Left) {
-            spaceship.update_position(Vec3::new(-0.1, 0.0, 0.0));
+            scene.move_spaceship(Vec3::new(-0.1, 0.0, 0.0));
         }
         if window.is_key_down(Key::Right) {
-            spaceship.update_position(Vec3::new(0.1, 0.0, 0.0));
+            scene.move_spaceship(Vec3::new(0.1, 0.0, 0.0));
         }
         if window.is_key_down(Key::Up) {
-            spaceship.update_position(Vec3::new(0.0, 0.1, 0.0));
+            scene.move_spaceship(Vec3::new(0.0, 0.1, 0.0));
         }
         if window.is_key_down(Key::Down) {
-            spaceship.update_position(Vec3::new(0.0, -0.1, 0.0));
+            scene.move_spaceship(Vec3::new(0.0, -0.1, 0.0));
         }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,207 @@
+use nalgebra_glm::{mat4_to_mat3, Mat3, Mat4, Vec2, Vec3};
+use std::sync::Arc;
+use crate::fragment::Fragment;
+use crate::light::Surface;
+use crate::texture::Texture;
+
+/// Exponente de Blinn-Phong con el que brillan los puntos de un mapa especular.
+pub const DEFAULT_SHININESS: f32 = 32.0;
+
+// Paso de las diferencias centrales del relieve procedural, en el espacio del objeto
+const RELIEF_STEP: f32 = 0.01;
+
+/// Imágenes que cambian cómo la superficie de un objeto recibe la luz, sin cambiar su
+/// color. Todas se muestrean con las coordenadas de textura del fragmento.
+#[derive(Clone, Debug)]
+pub struct SurfaceMaps {
+    /// Normales en el espacio tangente, codificadas como `rgb * 2 - 1`: rojo hacia donde
+    /// crece u, verde hacia arriba en la imagen y azul hacia afuera.
+    pub normal: Option<Arc<Texture>>,
+    /// Alturas (el promedio de los canales); la superficie se inclina según su pendiente.
+    pub bump: Option<Arc<Texture>>,
+    /// Cuánto inclina la normal una diferencia de altura de 1 entre texels vecinos.
+    pub bump_scale: f32,
+    /// Intensidad del brillo especular en cada punto: claro en los océanos, negro en la
+    /// tierra. Reemplaza al especular del shader.
+    pub specular: Option<Arc<Texture>>,
+    /// Exponente de Blinn-Phong para el brillo del mapa especular.
+    pub shininess: f32,
+}
+
+impl Default for SurfaceMaps {
+    fn default() -> Self {
+        SurfaceMaps { normal: None, bump: None, bump_scale: 1.0, specular: None, shininess: DEFAULT_SHININESS }
+    }
+}
+
+impl SurfaceMaps {
+    /// Normal del fragmento en el mundo con los mapas de normales y de relieve aplicados.
+    pub fn perturb_normal(&self, fragment: &Fragment) -> Vec3 {
+        if self.normal.is_none() && self.bump.is_none() {
+            return fragment.normal;
+        }
+        let (mut tangent, mut bitangent, mut normal) = tangent_frame(fragment);
+
+        if let Some(map) = &self.normal {
+            let texel = map.sample_rgb(fragment.tex_coords, fragment.tex_coords_dx, fragment.tex_coords_dy) * 2.0 - Vec3::repeat(1.0);
+            normal = (tangent * texel.x + bitangent * texel.y + normal * texel.z).normalize();
+            // El relieve se aplica sobre la normal ya perturbada
+            tangent = (tangent - normal * normal.dot(&tangent)).normalize();
+            bitangent = normal.cross(&tangent) * bitangent.dot(&normal.cross(&tangent)).signum();
+        }
+
+        if let Some(map) = &self.bump {
+            let (dx, dy) = (fragment.tex_coords_dx, fragment.tex_coords_dy);
+            let height = |offset: Vec2| mean(map.sample_rgb(fragment.tex_coords + offset, dx, dy));
+            let (du, dv) = (Vec2::new(1.0 / map.width() as f32, 0.0), Vec2::new(0.0, 1.0 / map.height() as f32));
+            let slope_u = (height(du) - height(-du)) / 2.0;
+            // v crece hacia abajo y la bitangente apunta hacia arriba
+            let slope_up = (height(-dv) - height(dv)) / 2.0;
+            normal = (normal - (tangent * slope_u + bitangent * slope_up) * self.bump_scale).normalize();
+        }
+
+        normal
+    }
+
+    /// `surface` con el brillo del mapa especular en el fragmento, si hay uno.
+    pub fn surface(&self, fragment: &Fragment, surface: Surface) -> Surface {
+        match &self.specular {
+            Some(map) => {
+                let specular = mean(map.sample_rgb(fragment.tex_coords, fragment.tex_coords_dx, fragment.tex_coords_dy));
+                Surface::new(specular, self.shininess)
+            }
+            None => surface,
+        }
+    }
+}
+
+/// Base ortonormal del espacio tangente en el mundo: tangente (hacia donde crece u),
+/// bitangente (hacia arriba en la imagen) y normal. Si el modelo no tiene coordenadas
+/// de textura, la tangente es una dirección cualquiera sobre la superficie.
+pub fn tangent_frame(fragment: &Fragment) -> (Vec3, Vec3, Vec3) {
+    let normal = fragment.normal.normalize();
+    let tangent = fragment.tangent.xyz();
+    let mut tangent = tangent - normal * normal.dot(&tangent);
+    if tangent.norm() < 1e-6 {
+        let axis = if normal.x.abs() < 0.9 { Vec3::x() } else { Vec3::y() };
+        tangent = axis - normal * normal.dot(&axis);
+    }
+    let tangent = tangent.normalize();
+    let handedness = if fragment.tangent.w < 0.0 { -1.0 } else { 1.0 };
+    (tangent, normal.cross(&tangent) * handedness, normal)
+}
+
+/// Normal `base` inclinada por un relieve procedural: `height` da la altura en cada punto
+/// del espacio del objeto (como `fragment.vertex_position`) y `strength` escala su
+/// pendiente. Así los shaders pueden sombrear con el mismo ruido que usan para el color;
+/// con `base` = [`SurfaceMaps::perturb_normal`] el relieve se suma al de los mapas.
+pub fn relief_normal(fragment: &Fragment, base: Vec3, model_matrix: &Mat4, strength: f32, height: impl Fn(Vec3) -> f32) -> Vec3 {
+    if strength == 0.0 {
+        return base;
+    }
+    let p = fragment.vertex_position;
+    let slope = |axis: Vec3| (height(p + axis * RELIEF_STEP) - height(p - axis * RELIEF_STEP)) / (2.0 * RELIEF_STEP);
+    let gradient = Vec3::new(slope(Vec3::x()), slope(Vec3::y()), slope(Vec3::z()));
+    if gradient.norm() < 1e-6 {
+        return base;
+    }
+
+    // El gradiente se transforma como las normales; se conserva su largo en el espacio
+    // del objeto para que el relieve no dependa del tamaño del planeta
+    let normal_matrix = mat4_to_mat3(model_matrix).transpose().try_inverse().unwrap_or(Mat3::identity());
+    let world_gradient = normal_matrix * gradient;
+    let world_gradient = world_gradient * (gradient.norm() / world_gradient.norm().max(1e-12));
+
+    let normal = base.normalize();
+    let along_surface = world_gradient - normal * normal.dot(&world_gradient);
+    (normal - along_surface * strength).normalize()
+}
+
+fn mean(rgb: Vec3) -> f32 {
+    (rgb.x + rgb.y + rgb.z) / 3.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nalgebra_glm::Vec4;
+    use crate::color::Color;
+
+    // Fragmento en un plano que mira hacia +Z, con u hacia +X y la imagen derecha
+    fn flat_fragment(tex_coords: Vec2) -> Fragment {
+        Fragment::new(0.0, 0.0, Color::black(), 0.0, Vec3::z(), Vec3::zeros(), Vec3::zeros())
+            .with_tex_coords(tex_coords, Vec2::zeros(), Vec2::zeros())
+            .with_tangent(Vec4::new(1.0, 0.0, 0.0, 1.0))
+    }
+
+    fn filled(width: usize, height: usize, pixel: impl Fn(usize, usize) -> u32) -> Arc<Texture> {
+        let pixels: Vec<u32> = (0..width * height).map(|i| pixel(i % width, i / width)).collect();
+        Arc::new(Texture::from_pixels(width, height, &pixels))
+    }
+
+    #[test]
+    fn normal_maps_are_in_tangent_space() {
+        let fragment = flat_fragment(Vec2::new(0.5, 0.5));
+        let (tangent, bitangent, _) = tangent_frame(&fragment);
+        assert_eq!((tangent, bitangent), (Vec3::x(), Vec3::y()));
+
+        // El azul plano deja la normal como está
+        let flat = SurfaceMaps { normal: Some(filled(4, 4, |_, _| 0x8080FF)), ..SurfaceMaps::default() };
+        assert!((flat.perturb_normal(&fragment) - Vec3::z()).norm() < 0.01);
+
+        // Con rojo, se inclina hacia donde crece u; con la textura espejada, verde
+        // inclina hacia abajo
+        let tilted = SurfaceMaps { normal: Some(filled(4, 4, |_, _| 0xFF80B4)), ..SurfaceMaps::default() };
+        assert!(tilted.perturb_normal(&fragment).x > 0.5);
+        let up = SurfaceMaps { normal: Some(filled(4, 4, |_, _| 0x80FFB4)), ..SurfaceMaps::default() };
+        let mirrored = flat_fragment(Vec2::new(0.5, 0.5)).with_tangent(Vec4::new(1.0, 0.0, 0.0, -1.0));
+        assert!(up.perturb_normal(&fragment).y > 0.5);
+        assert!(up.perturb_normal(&mirrored).y < -0.5);
+    }
+
+    #[test]
+    fn bumps_tilt_away_from_the_slope() {
+        // Rampa que sube hacia la derecha de la imagen
+        let ramp = filled(8, 8, |x, _| 0x010101 * (x as u32 * 32));
+        let maps = SurfaceMaps { bump: Some(ramp), bump_scale: 4.0, ..SurfaceMaps::default() };
+        let normal = maps.perturb_normal(&flat_fragment(Vec2::new(0.5, 0.5)));
+
+        assert!(normal.x < -0.3 && normal.y.abs() < 1e-4 && normal.z > 0.0, "{:?}", normal);
+    }
+
+    #[test]
+    fn specular_maps_replace_the_shader_surface() {
+        let glossy = SurfaceMaps { specular: Some(filled(2, 1, |x, _| if x == 0 { 0xFFFFFF } else { 0x000000 })), ..SurfaceMaps::default() };
+        let ocean = glossy.surface(&flat_fragment(Vec2::new(0.25, 0.5)), Surface::MATTE);
+        let land = glossy.surface(&flat_fragment(Vec2::new(0.75, 0.5)), Surface::MATTE);
+
+        assert_eq!(ocean, Surface::new(1.0, DEFAULT_SHININESS));
+        assert_eq!(land.specular, 0.0);
+        assert_eq!(SurfaceMaps::default().surface(&flat_fragment(Vec2::zeros()), Surface::MATTE), Surface::MATTE);
+    }
+
+    #[test]
+    fn procedural_relief_follows_the_height_gradient() {
+        let fragment = flat_fragment(Vec2::zeros());
+        // Sube hacia +Y: la normal se inclina hacia -Y, aunque el objeto esté escalado
+        let scaled = Mat4::new_scaling(3.0);
+        let normal = relief_normal(&fragment, fragment.normal, &scaled, 1.0, |p| p.y);
+
+        assert!((normal - Vec3::new(0.0, -1.0, 1.0).normalize()).norm() < 1e-4, "{:?}", normal);
+        assert_eq!(relief_normal(&fragment, fragment.normal, &scaled, 0.0, |p| p.y), Vec3::z());
+        assert_eq!(relief_normal(&fragment, fragment.normal, &scaled, 1.0, |_| 0.5), Vec3::z());
+    }
+
+    #[test]
+    fn procedural_relief_adds_to_the_maps() {
+        // El mapa de relieve inclina hacia -X y el procedural hacia -Y: se ven los dos
+        let ramp = filled(8, 8, |x, _| 0x010101 * (x as u32 * 32));
+        let maps = SurfaceMaps { bump: Some(ramp), bump_scale: 4.0, ..SurfaceMaps::default() };
+        let fragment = flat_fragment(Vec2::new(0.5, 0.5));
+        let base = maps.perturb_normal(&fragment);
+        let normal = relief_normal(&fragment, base, &Mat4::identity(), 1.0, |p| p.y);
+
+        assert!((normal.x - base.x).abs() < 0.2 && normal.x < -0.2, "{:?} {:?}", base, normal);
+        assert!(normal.y < -0.3 && normal.z > 0.0, "{:?}", normal);
+    }
+}
@@ -0,0 +1,128 @@
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+use crate::color::Color;
+use crate::light::Surface;
+use crate::maps::SurfaceMaps;
+use crate::texture::Texture;
+
+/// Material de un archivo .mtl, con el que se dibuja una parte de un modelo (ver
+/// [`crate::obj::MeshPart`]).
+#[derive(Clone, Debug)]
+pub struct Material {
+    pub name: String,
+    pub diffuse: Color,  // Kd
+    pub specular: f32,   // Ks, promedio de los canales
+    pub shininess: f32,  // Ns
+    pub opacity: f32,    // d: 1 opaco, 0 invisible
+    pub diffuse_map: Option<Arc<Texture>>, // map_Kd, multiplicado por `diffuse`
+    pub bump_map: Option<Arc<Texture>>,    // map_Bump, alturas en escala de grises
+    pub bump_scale: f32, // La opción -bm de map_Bump
+}
+
+impl Material {
+    /// Material leído por tobj. Las imágenes se buscan en `directory`, junto al .obj.
+    pub fn from_mtl(material: &tobj::Material, directory: &Path) -> io::Result<Self> {
+        let [r, g, b] = material.diffuse.unwrap_or([0.8, 0.8, 0.8]);
+        let specular = material.specular.map_or(0.0, |[r, g, b]| (r + g + b) / 3.0);
+        let load = |file: &str| -> io::Result<Arc<Texture>> {
+            let path = directory.join(file);
+            Texture::load(&path)
+                .map(Arc::new)
+                .map_err(|error| io::Error::new(error.kind(), format!("{}: {}", path.display(), error)))
+        };
+
+        let (bump_map, bump_scale) = match &material.normal_texture {
+            Some(map) => {
+                let (file, scale) = parse_bump(map);
+                (Some(load(file)?), scale)
+            }
+            None => (None, 1.0),
+        };
+
+        Ok(Material {
+            name: material.name.clone(),
+            diffuse: Color::from_vec3([r, g, b].into()),
+            specular,
+            shininess: material.shininess.unwrap_or(1.0),
+            opacity: material.dissolve.unwrap_or(1.0).clamp(0.0, 1.0),
+            diffuse_map: material.diffuse_texture.as_deref().map(load).transpose()?,
+            bump_map,
+            bump_scale,
+        })
+    }
+
+    /// Brillo especular para [`crate::light::shade`].
+    pub fn surface(&self) -> Surface {
+        Surface::new(self.specular, self.shininess.max(1.0))
+    }
+
+    /// Imagen para `Uniforms::texture`: la del material o, si no tiene, la del objeto.
+    pub fn texture(&self, texture: Option<&Arc<Texture>>) -> Option<Arc<Texture>> {
+        self.diffuse_map.as_ref().or(texture).cloned()
+    }
+
+    /// Los mapas del objeto con el relieve del material, si tiene uno.
+    pub fn maps(&self, maps: &SurfaceMaps) -> SurfaceMaps {
+        match &self.bump_map {
+            Some(bump) => SurfaceMaps { bump: Some(bump.clone()), bump_scale: self.bump_scale, ..maps.clone() },
+            None => maps.clone(),
+        }
+    }
+}
+
+// Separa la imagen de map_Bump de su escala: "-bm 0.5 relieve.png". Las demás opciones
+// del formato se ignoran
+fn parse_bump(map: &str) -> (&str, f32) {
+    let mut words = map.split_whitespace();
+    let mut scale = 1.0;
+    let mut file = "";
+    while let Some(word) = words.next() {
+        match word {
+            "-bm" => scale = words.next().and_then(|value| value.parse().ok()).unwrap_or(scale),
+            word => file = word,
+        }
+    }
+    (file, scale)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::obj::Obj;
+
+    #[test]
+    fn model_parts_keep_their_materials() {
+        let model = Obj::load("tests/fixtures/two_materials.obj").expect("Failed to load fixture");
+        let parts = model.get_mesh_parts();
+        let names: Vec<_> = parts.iter().map(|part| part.material.as_ref().unwrap().name.as_str()).collect();
+        assert_eq!(names, ["Casco", "Vidrio"]);
+        assert!(parts.iter().all(|part| part.vertices.len() == 6));
+        assert_eq!(parts.iter().map(|part| part.vertices.len()).sum::<usize>(), model.get_vertex_array().len());
+
+        let hull = parts[0].material.as_ref().unwrap();
+        assert_eq!(hull.diffuse, Color::new(102, 153, 204));
+        assert_eq!(hull.surface(), Surface::new(0.5, 64.0));
+        assert_eq!(hull.opacity, 1.0);
+        assert_eq!(hull.diffuse_map.as_ref().map(|map| map.width()), Some(2));
+        assert!(hull.bump_map.is_none());
+
+        let glass = parts[1].material.as_ref().unwrap();
+        assert_eq!(glass.opacity, 0.5);
+        assert!(glass.diffuse_map.is_none());
+        assert!(glass.bump_map.is_some());
+        assert_eq!(glass.bump_scale, 0.25);
+
+        // Sin su .mtl, los modelos se cargan igual, sin materiales
+        for path in ["assets/models/ship.obj", "assets/models/sphere.obj"] {
+            let model = Obj::load(path).expect("Failed to load obj");
+            assert!(model.get_mesh_parts().iter().all(|part| part.material.is_none()), "{}", path);
+        }
+    }
+
+    #[test]
+    fn bump_maps_take_their_scale() {
+        assert_eq!(parse_bump("relieve.png"), ("relieve.png", 1.0));
+        assert_eq!(parse_bump("-bm 0.25 relieve.png"), ("relieve.png", 0.25));
+    }
+}
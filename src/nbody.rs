@@ -0,0 +1,301 @@
+use nalgebra_glm::DVec3;
+use std::fmt;
+
+/// Integrador simpléctico para [`NBody::step`]. Los dos son de segundo orden, conservan
+/// la energía sin deriva secular y son reversibles: con `dt` negativo se vuelve al
+/// estado anterior.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Integrator {
+    /// Leapfrog patada-deriva-patada: medio paso de velocidad, paso completo de
+    /// posición y otro medio paso de velocidad.
+    Leapfrog,
+    /// Verlet de posición (deriva-patada-deriva): la fuerza se evalúa en la mitad del
+    /// paso.
+    Verlet,
+}
+
+impl Integrator {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "leapfrog" => Some(Integrator::Leapfrog),
+            "verlet" => Some(Integrator::Verlet),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Integrator::Leapfrog => "leapfrog",
+            Integrator::Verlet => "verlet",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            Integrator::Leapfrog => Integrator::Verlet,
+            Integrator::Verlet => Integrator::Leapfrog,
+        }
+    }
+}
+
+/// Cuerpo puntual. Con masa 0 es una partícula de prueba: la gravedad la mueve, pero
+/// no atrae a los demás (así se simula la nave).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Particle {
+    pub mass: f64,
+    pub position: DVec3,
+    pub velocity: DVec3,
+}
+
+/// Energía, momento lineal y momento angular del sistema.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Diagnostics {
+    pub kinetic: f64,
+    pub potential: f64,
+    pub momentum: DVec3,
+    pub angular_momentum: DVec3,
+    pub energy_drift: f64, // Cambio relativo de la energía total desde el inicio
+}
+
+impl Diagnostics {
+    pub fn energy(&self) -> f64 {
+        self.kinetic + self.potential
+    }
+}
+
+impl fmt::Display for Diagnostics {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "E = {:.6} (K = {:.6}, U = {:.6}, deriva {:+.2e}), |p| = {:.3e}, |L| = {:.6}",
+            self.energy(),
+            self.kinetic,
+            self.potential,
+            self.energy_drift,
+            self.momentum.norm(),
+            self.angular_momentum.norm(),
+        )
+    }
+}
+
+/// Sistema de partículas bajo gravedad mutua.
+///
+/// Las posiciones y velocidades se guardan en f64: a escalas de tiempo altas se dan
+/// miles de pasos por frame y en f32 el error de redondeo se nota en la energía.
+#[derive(Clone, Debug)]
+pub struct NBody {
+    pub particles: Vec<Particle>,
+    pub gravity: f64,   // Constante gravitacional G
+    pub softening: f64, // Suaviza la fuerza a distancias menores que esta
+    integrator: Integrator,
+    accelerations: Option<Vec<DVec3>>, // Del final del último paso (leapfrog)
+    initial_energy: Option<f64>,
+}
+
+impl NBody {
+    pub fn new(gravity: f64, softening: f64, integrator: Integrator) -> Self {
+        NBody {
+            particles: Vec::new(),
+            gravity,
+            softening,
+            integrator,
+            accelerations: None,
+            initial_energy: None,
+        }
+    }
+
+    /// Agrega una partícula y devuelve su índice.
+    pub fn add(&mut self, particle: Particle) -> usize {
+        self.particles.push(particle);
+        self.accelerations = None;
+        self.initial_energy = None;
+        self.particles.len() - 1
+    }
+
+    pub fn integrator(&self) -> Integrator {
+        self.integrator
+    }
+
+    pub fn set_integrator(&mut self, integrator: Integrator) {
+        self.integrator = integrator;
+        self.accelerations = None;
+    }
+
+    /// Cambia la velocidad de una partícula (por ejemplo, el empuje de la nave).
+    pub fn add_velocity(&mut self, index: usize, delta: DVec3) {
+        self.particles[index].velocity += delta;
+    }
+
+    /// Resta la velocidad del centro de masa para que el sistema no se desplace.
+    pub fn remove_momentum(&mut self) {
+        let total_mass: f64 = self.particles.iter().map(|particle| particle.mass).sum();
+        if total_mass <= 0.0 {
+            return;
+        }
+        let momentum: DVec3 = self.particles.iter().map(|particle| particle.velocity * particle.mass).sum();
+        for particle in &mut self.particles {
+            particle.velocity -= momentum / total_mass;
+        }
+        self.initial_energy = None;
+    }
+
+    /// Avanza `dt` (negativo para retroceder).
+    pub fn step(&mut self, dt: f64) {
+        if self.initial_energy.is_none() {
+            self.initial_energy = Some(self.energy());
+        }
+
+        match self.integrator {
+            Integrator::Leapfrog => {
+                let accelerations = match self.accelerations.take() {
+                    Some(accelerations) => accelerations,
+                    None => self.accelerations(),
+                };
+                for (particle, acceleration) in self.particles.iter_mut().zip(&accelerations) {
+                    particle.velocity += acceleration * (dt / 2.0);
+                    particle.position += particle.velocity * dt;
+                }
+                let accelerations = self.accelerations();
+                for (particle, acceleration) in self.particles.iter_mut().zip(&accelerations) {
+                    particle.velocity += acceleration * (dt / 2.0);
+                }
+                self.accelerations = Some(accelerations);
+            }
+            Integrator::Verlet => {
+                for particle in &mut self.particles {
+                    particle.position += particle.velocity * (dt / 2.0);
+                }
+                let accelerations = self.accelerations();
+                for (particle, acceleration) in self.particles.iter_mut().zip(&accelerations) {
+                    particle.velocity += acceleration * dt;
+                    particle.position += particle.velocity * (dt / 2.0);
+                }
+            }
+        }
+    }
+
+    pub fn diagnostics(&self) -> Diagnostics {
+        let kinetic = self.particles.iter().map(|particle| 0.5 * particle.mass * particle.velocity.norm_squared()).sum();
+        let potential = self.potential();
+        let initial = self.initial_energy.unwrap_or(kinetic + potential);
+
+        Diagnostics {
+            kinetic,
+            potential,
+            momentum: self.particles.iter().map(|particle| particle.velocity * particle.mass).sum(),
+            angular_momentum: self
+                .particles
+                .iter()
+                .map(|particle| particle.position.cross(&particle.velocity) * particle.mass)
+                .sum(),
+            energy_drift: if initial != 0.0 { (kinetic + potential - initial) / initial.abs() } else { 0.0 },
+        }
+    }
+
+    fn energy(&self) -> f64 {
+        self.diagnostics().energy()
+    }
+
+    fn potential(&self) -> f64 {
+        let mut potential = 0.0;
+        for (i, a) in self.particles.iter().enumerate() {
+            for b in &self.particles[i + 1..] {
+                let distance = ((b.position - a.position).norm_squared() + self.softening * self.softening).sqrt();
+                potential -= self.gravity * a.mass * b.mass / distance;
+            }
+        }
+        potential
+    }
+
+    // Fuerza de cada par una sola vez; las partículas sin masa no atraen a nadie
+    fn accelerations(&self) -> Vec<DVec3> {
+        let mut accelerations = vec![DVec3::zeros(); self.particles.len()];
+        for i in 0..self.particles.len() {
+            for j in i + 1..self.particles.len() {
+                let (a, b) = (&self.particles[i], &self.particles[j]);
+                let offset = b.position - a.position;
+                let distance_squared = offset.norm_squared() + self.softening * self.softening;
+                let pull = offset * (self.gravity / (distance_squared * distance_squared.sqrt()));
+                accelerations[i] += pull * b.mass;
+                accelerations[j] -= pull * a.mass;
+            }
+        }
+        accelerations
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Sol y planeta en órbita circular alrededor del centro de masa
+    fn binary(integrator: Integrator) -> NBody {
+        let mut system = NBody::new(1.0, 0.0, integrator);
+        system.add(Particle { mass: 100.0, position: DVec3::zeros(), velocity: DVec3::zeros() });
+        system.add(Particle { mass: 1.0, position: DVec3::new(5.0, 0.0, 0.0), velocity: DVec3::new(0.0, 0.0, (101.0f64 / 5.0).sqrt()) });
+        system.remove_momentum();
+        system
+    }
+
+    #[test]
+    fn energy_and_momentum_are_conserved() {
+        for integrator in [Integrator::Leapfrog, Integrator::Verlet] {
+            let mut system = binary(integrator);
+            let start = system.diagnostics();
+
+            // Unas 10 vueltas
+            for _ in 0..14_000 {
+                system.step(1.0 / 100.0);
+            }
+            let end = system.diagnostics();
+
+            assert!(end.energy_drift.abs() < 1e-6, "{:?}: deriva {}", integrator, end.energy_drift);
+            assert!(end.momentum.norm() < 1e-9);
+            assert!((end.angular_momentum - start.angular_momentum).norm() < 1e-9);
+            // Sigue en órbita circular
+            let radius = (system.particles[1].position - system.particles[0].position).norm();
+            assert!((radius - 5.0).abs() < 1e-3, "{:?}: radio {}", integrator, radius);
+        }
+    }
+
+    #[test]
+    fn integration_is_time_reversible() {
+        for integrator in [Integrator::Leapfrog, Integrator::Verlet] {
+            let mut system = binary(integrator);
+            let start = system.particles.clone();
+
+            for _ in 0..500 {
+                system.step(0.01);
+            }
+            for _ in 0..500 {
+                system.step(-0.01);
+            }
+
+            for (particle, initial) in system.particles.iter().zip(&start) {
+                assert!((particle.position - initial.position).norm() < 1e-9);
+                assert!((particle.velocity - initial.velocity).norm() < 1e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn test_particles_feel_gravity_without_pulling() {
+        let mut system = binary(Integrator::Leapfrog);
+        let without_probe = {
+            let mut system = system.clone();
+            for _ in 0..10 {
+                system.step(0.01);
+            }
+            system.particles
+        };
+
+        let probe = system.add(Particle { mass: 0.0, position: DVec3::new(0.0, 0.0, 3.0), velocity: DVec3::zeros() });
+        for _ in 0..10 {
+            system.step(0.01);
+        }
+
+        // La sonda cae hacia el sol y los demás no se enteran
+        assert!((system.particles[probe].position - system.particles[0].position).norm() < 2.95);
+        assert_eq!(&system.particles[..2], &without_probe[..]);
+    }
+}
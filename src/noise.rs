@@ -0,0 +1,127 @@
+use fastnoise_lite::{CellularDistanceFunction, CellularReturnType, DomainWarpType, FastNoiseLite, FractalType, NoiseType};
+use nalgebra_glm::Vec3;
+use crate::params::{ParamValue, ShaderParams};
+
+/// Configuración de los fractales. Un shader la ajusta declarando parámetros con los
+/// mismos nombres (`seed`, `octaves`, `lacunarity`, `gain`, `warp_amplitude`).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NoiseSettings {
+    pub seed: i32,
+    pub octaves: i32,
+    pub lacunarity: f32,
+    pub gain: f32,
+    pub warp_amplitude: f32,
+}
+
+impl Default for NoiseSettings {
+    fn default() -> Self {
+        NoiseSettings {
+            seed: 1337,
+            octaves: 5,
+            lacunarity: 2.0,
+            gain: 0.5,
+            warp_amplitude: 0.4,
+        }
+    }
+}
+
+impl NoiseSettings {
+    /// Valores por defecto reemplazados por los parámetros del shader que existan.
+    pub fn from_params(params: &ShaderParams) -> Self {
+        let defaults = NoiseSettings::default();
+        let float = |name, default| match params.get(name) {
+            Some(ParamValue::Float(value)) => value,
+            _ => default,
+        };
+        let int = |name, default| match params.get(name) {
+            Some(ParamValue::Int(value)) => value,
+            _ => default,
+        };
+
+        NoiseSettings {
+            seed: int("seed", defaults.seed),
+            octaves: int("octaves", defaults.octaves),
+            lacunarity: float("lacunarity", defaults.lacunarity),
+            gain: float("gain", defaults.gain),
+            warp_amplitude: float("warp_amplitude", defaults.warp_amplitude),
+        }
+    }
+}
+
+/// Ruido 3D para superficies, muestreado sobre la posición en espacio del objeto: el
+/// patrón no tiene costuras, no se refleja en la cara trasera y gira con el cuerpo.
+///
+/// Todos los ruidos usan frecuencia 1, así que la escala la da el punto de muestreo.
+/// FastNoiseLite no tiene ruido 4D; para animar, los shaders desplazan el punto de
+/// muestreo por el campo 3D con el tiempo.
+pub struct SurfaceNoise {
+    fbm: FastNoiseLite,
+    ridged: FastNoiseLite,
+    warp: FastNoiseLite,
+    cellular: FastNoiseLite,
+}
+
+impl SurfaceNoise {
+    pub fn new(settings: NoiseSettings) -> Self {
+        let fractal = |fractal_type| {
+            let mut noise = FastNoiseLite::with_seed(settings.seed);
+            noise.set_noise_type(Some(NoiseType::OpenSimplex2));
+            noise.set_frequency(Some(1.0));
+            noise.set_fractal_type(Some(fractal_type));
+            noise.set_fractal_octaves(Some(settings.octaves));
+            noise.set_fractal_lacunarity(Some(settings.lacunarity));
+            noise.set_fractal_gain(Some(settings.gain));
+            noise
+        };
+
+        // La deformación del dominio no necesita tanto detalle como el ruido que deforma
+        let mut warp = fractal(FractalType::DomainWarpProgressive);
+        warp.set_fractal_octaves(Some(settings.octaves.min(3)));
+        warp.set_domain_warp_type(Some(DomainWarpType::OpenSimplex2));
+        warp.set_domain_warp_amp(Some(settings.warp_amplitude));
+
+        let mut cellular = fractal(FractalType::None);
+        cellular.set_noise_type(Some(NoiseType::Cellular));
+        cellular.set_cellular_distance_function(Some(CellularDistanceFunction::EuclideanSq));
+        cellular.set_cellular_return_type(Some(CellularReturnType::Distance));
+
+        SurfaceNoise {
+            fbm: fractal(FractalType::FBm),
+            ridged: fractal(FractalType::Ridged),
+            warp,
+            cellular,
+        }
+    }
+
+    pub fn from_params(params: &ShaderParams) -> Self {
+        SurfaceNoise::new(NoiseSettings::from_params(params))
+    }
+
+    /// Movimiento browniano fraccional, aproximadamente en [-1, 1].
+    pub fn fbm(&self, p: Vec3) -> f32 {
+        self.fbm.get_noise_3d(p.x, p.y, p.z)
+    }
+
+    /// Fractal de crestas: valores altos en líneas finas, como cordilleras o filamentos.
+    pub fn ridged(&self, p: Vec3) -> f32 {
+        self.ridged.get_noise_3d(p.x, p.y, p.z)
+    }
+
+    /// Distancia a la celda de Voronoi más cercana, en [-1, 1]; bajo en el centro de
+    /// cada celda.
+    pub fn cellular(&self, p: Vec3) -> f32 {
+        self.cellular.get_noise_3d(p.x, p.y, p.z)
+    }
+
+    /// Desplaza el punto con ruido, para deformar el patrón que se muestree ahí.
+    pub fn warp(&self, p: Vec3) -> Vec3 {
+        let (x, y, z) = self.warp.domain_warp_3d(p.x, p.y, p.z);
+        Vec3::new(x, y, z)
+    }
+}
+
+impl Default for SurfaceNoise {
+    fn default() -> Self {
+        SurfaceNoise::new(NoiseSettings::default())
+    }
+}
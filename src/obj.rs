@@ -1,7 +1,12 @@
-use tobj;
-use nalgebra_glm::{Vec2, Vec3};
+use nalgebra_glm::{Vec2, Vec3, Vec4};
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+use crate::color::Color;
+use crate::material::Material;
 use crate::vertex::Vertex;
 
+/// Modelo cargado desde un archivo .obj, con los materiales de su .mtl.
 pub struct Obj {
     meshes: Vec<Mesh>,
 }
@@ -10,16 +15,35 @@ struct Mesh {
     vertices: Vec<Vec3>,
     normals: Vec<Vec3>,
     texcoords: Vec<Vec2>,
+    colors: Vec<Color>, // Vacío si el archivo no trae colores por vértice
     indices: Vec<u32>,
+    material: Option<Arc<Material>>,
+}
+
+/// Parte de un modelo con un solo material, lista para `render`.
+pub struct MeshPart {
+    pub vertices: Vec<Vertex>,
+    pub material: Option<Arc<Material>>, // `None` si el .obj no indica uno o falta el .mtl
 }
 
 impl Obj {
-    pub fn load(filename: &str) -> Result<Self, tobj::LoadError> {
-        let (models, _) = tobj::load_obj(filename, &tobj::LoadOptions {
+    /// Lee el modelo y su .mtl. Si el .mtl no existe, el modelo se carga sin materiales;
+    /// si una imagen del .mtl no se puede leer, es un error.
+    pub fn load(filename: impl AsRef<Path>) -> io::Result<Self> {
+        let filename = filename.as_ref();
+        let (models, materials) = tobj::load_obj(filename, &tobj::LoadOptions {
             single_index: true,
             triangulate: true,
             ..Default::default()
-        })?;
+        })
+        .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+
+        let directory = filename.parent().unwrap_or(Path::new(""));
+        let materials = materials
+            .unwrap_or_default()
+            .iter()
+            .map(|material| Material::from_mtl(material, directory).map(Arc::new))
+            .collect::<io::Result<Vec<_>>>()?;
 
         let meshes = models.into_iter().map(|model| {
             let mesh = model.mesh;
@@ -33,30 +57,123 @@ impl Obj {
                 texcoords: mesh.texcoords.chunks(2)
                     .map(|t| Vec2::new(t[0], 1.0 - t[1]))
                     .collect(),
+                colors: mesh.vertex_color.chunks(3)
+                    .map(|c| Color::from_vec3(Vec3::new(c[0], c[1], c[2])))
+                    .collect(),
                 indices: mesh.indices,
+                material: mesh.material_id.and_then(|id| materials.get(id).cloned()),
             }
         }).collect();
 
         Ok(Obj { meshes })
     }
 
+    /// Lista plana de vértices, de a tres por triángulo, lista para `render`. Junta
+    /// todas las partes: los materiales se pierden.
     pub fn get_vertex_array(&self) -> Vec<Vertex> {
-        let mut vertices = Vec::new();
+        self.meshes.iter().flat_map(Mesh::vertex_array).collect()
+    }
 
-        for mesh in &self.meshes {
-            for &index in &mesh.indices {
-                let position = mesh.vertices[index as usize];
-                let normal = mesh.normals.get(index as usize)
+    /// Una lista de vértices por cada parte del modelo, con su material.
+    pub fn get_mesh_parts(&self) -> Vec<MeshPart> {
+        self.meshes
+            .iter()
+            .map(|mesh| MeshPart { vertices: mesh.vertex_array(), material: mesh.material.clone() })
+            .collect()
+    }
+}
+
+impl Mesh {
+    fn vertex_array(&self) -> Vec<Vertex> {
+        let tangents = self.tangents();
+        self.indices
+            .iter()
+            .map(|&index| {
+                let position = self.vertices[index as usize];
+                let normal = self.normals.get(index as usize)
                     .cloned()
                     .unwrap_or(Vec3::new(0.0, 1.0, 0.0));
-                let tex_coords = mesh.texcoords.get(index as usize)
+                let tex_coords = self.texcoords.get(index as usize)
                     .cloned()
                     .unwrap_or(Vec2::new(0.0, 0.0));
 
-                vertices.push(Vertex::new(position, normal, tex_coords));
+                let mut vertex = Vertex::new(position, normal, tex_coords);
+                vertex.tangent = tangents[index as usize];
+                if let Some(&color) = self.colors.get(index as usize) {
+                    vertex.color = color;
+                }
+                vertex
+            })
+            .collect()
+    }
+
+    // Tangente de cada vértice: la dirección en que crece u sobre la superficie, promedio
+    // de los triángulos que lo comparten y perpendicular a la normal. En w, +1 si la
+    // bitangente (normal × tangente) apunta hacia arriba en la imagen (v decreciente) y
+    // -1 si la textura está espejada. Sin coordenadas de textura queda en cero.
+    fn tangents(&self) -> Vec<Vec4> {
+        let mut tangents = vec![Vec3::zeros(); self.vertices.len()];
+        let mut bitangents = vec![Vec3::zeros(); self.vertices.len()];
+        if self.texcoords.len() < self.vertices.len() {
+            return vec![Vec4::zeros(); self.vertices.len()];
+        }
+
+        for triangle in self.indices.chunks(3).filter(|triangle| triangle.len() == 3) {
+            let [a, b, c] = [triangle[0] as usize, triangle[1] as usize, triangle[2] as usize];
+            let (edge1, edge2) = (self.vertices[b] - self.vertices[a], self.vertices[c] - self.vertices[a]);
+            let (duv1, duv2) = (self.texcoords[b] - self.texcoords[a], self.texcoords[c] - self.texcoords[a]);
+            let determinant = duv1.x * duv2.y - duv2.x * duv1.y;
+            if determinant.abs() < 1e-12 {
+                continue;
+            }
+
+            // Derivadas de la posición respecto a u y a v (v crece hacia abajo en la imagen)
+            let along_u = (edge1 * duv2.y - edge2 * duv1.y) / determinant;
+            let along_v = (edge2 * duv1.x - edge1 * duv2.x) / determinant;
+            for index in [a, b, c] {
+                tangents[index] += along_u;
+                bitangents[index] += along_v;
             }
         }
 
-        vertices
+        tangents
+            .iter()
+            .zip(&bitangents)
+            .enumerate()
+            .map(|(index, (tangent, along_v))| {
+                let normal = self.normals.get(index).copied().unwrap_or(Vec3::y()).normalize();
+                let tangent = tangent - normal * normal.dot(tangent);
+                if tangent.norm() < 1e-6 {
+                    return Vec4::zeros();
+                }
+                let tangent = tangent.normalize();
+                let handedness = if normal.cross(&tangent).dot(along_v) > 0.0 { -1.0 } else { 1.0 };
+                Vec4::new(tangent.x, tangent.y, tangent.z, handedness)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sphere_tangents_point_east() {
+        let sphere = Obj::load("assets/models/sphere.obj").expect("Failed to load obj");
+
+        for vertex in sphere.get_vertex_array() {
+            // Lejos de los polos, donde la dirección este está definida
+            if vertex.position.y.abs() > 0.9 * vertex.position.norm() {
+                continue;
+            }
+            let tangent = vertex.tangent.xyz();
+            let east = Vec3::y().cross(&vertex.position).normalize();
+
+            assert!(tangent.dot(&vertex.normal).abs() < 1e-4);
+            assert!(tangent.dot(&east) > 0.95, "{:?} en {:?}", tangent, vertex.position);
+            // La bitangente apunta al norte: la imagen no está espejada
+            assert_eq!(vertex.tangent.w, 1.0);
+        }
     }
 }
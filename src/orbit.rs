@@ -0,0 +1,201 @@
+use nalgebra_glm::Vec3;
+use std::f32::consts::PI;
+
+/// Elementos keplerianos de una órbita. Los ángulos están en radianes y el plano de
+/// referencia es XZ, con +Y como normal (el "norte" de la eclíptica).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct OrbitalElements {
+    pub semi_major_axis: f32,
+    pub eccentricity: f32,
+    pub inclination: f32,
+    pub longitude_of_ascending_node: f32,
+    pub argument_of_periapsis: f32,
+    pub mean_anomaly_at_epoch: f32,
+}
+
+impl OrbitalElements {
+    /// Órbita circular en el plano XZ (e = 0, i = 0) que empieza sobre +X.
+    pub fn circular(radius: f32) -> Self {
+        OrbitalElements {
+            semi_major_axis: radius,
+            eccentricity: 0.0,
+            inclination: 0.0,
+            longitude_of_ascending_node: 0.0,
+            argument_of_periapsis: 0.0,
+            mean_anomaly_at_epoch: 0.0,
+        }
+    }
+
+    /// Igual que construir los elementos a mano, pero con los ángulos en grados, como
+    /// aparecen en las tablas de efemérides.
+    pub fn from_degrees(
+        semi_major_axis: f32,
+        eccentricity: f32,
+        inclination: f32,
+        longitude_of_ascending_node: f32,
+        argument_of_periapsis: f32,
+        mean_anomaly_at_epoch: f32,
+    ) -> Self {
+        OrbitalElements {
+            semi_major_axis,
+            eccentricity,
+            inclination: inclination.to_radians(),
+            longitude_of_ascending_node: longitude_of_ascending_node.to_radians(),
+            argument_of_periapsis: argument_of_periapsis.to_radians(),
+            mean_anomaly_at_epoch: mean_anomaly_at_epoch.to_radians(),
+        }
+    }
+
+    /// Posición relativa al foco cuando la anomalía media avanzó `elapsed` radianes
+    /// desde la época.
+    pub fn position(&self, elapsed: f32) -> Vec3 {
+        let (eccentric_anomaly, true_anomaly) = self.anomalies(elapsed);
+        let distance = self.semi_major_axis * (1.0 - self.eccentricity * eccentric_anomaly.cos());
+
+        let (sin_latitude, cos_latitude) = (self.argument_of_periapsis + true_anomaly).sin_cos();
+        self.in_reference_plane(distance * cos_latitude, distance * sin_latitude)
+    }
+
+    /// Velocidad en el mismo instante que [`OrbitalElements::position`] para un cuerpo
+    /// que cae libremente hacia un foco de parámetro gravitacional `mu` (G por la masa
+    /// total). No depende de la velocidad de la órbita guionada: con `mu` se obtiene la
+    /// que pide la gravedad.
+    pub fn velocity(&self, elapsed: f32, mu: f32) -> Vec3 {
+        let e = self.eccentricity;
+        if mu <= 0.0 || self.semi_major_axis <= 0.0 {
+            return Vec3::zeros();
+        }
+        let (_, true_anomaly) = self.anomalies(elapsed);
+
+        // En el plano de la órbita con el periapsis sobre el primer eje, y girada ω
+        let speed = (mu / (self.semi_major_axis * (1.0 - e * e))).sqrt();
+        let (along, across) = (-speed * true_anomaly.sin(), speed * (e + true_anomaly.cos()));
+        let (sin_periapsis, cos_periapsis) = self.argument_of_periapsis.sin_cos();
+        self.in_reference_plane(
+            along * cos_periapsis - across * sin_periapsis,
+            along * sin_periapsis + across * cos_periapsis,
+        )
+    }
+
+    // Anomalías excéntrica y verdadera
+    fn anomalies(&self, elapsed: f32) -> (f32, f32) {
+        let e = self.eccentricity;
+        let eccentric_anomaly = solve_kepler(self.mean_anomaly_at_epoch + elapsed, e);
+        let true_anomaly = 2.0 * f32::atan2(
+            (1.0 + e).sqrt() * (eccentric_anomaly / 2.0).sin(),
+            (1.0 - e).sqrt() * (eccentric_anomaly / 2.0).cos(),
+        );
+        (eccentric_anomaly, true_anomaly)
+    }
+
+    // Del plano de la órbita (primer eje sobre la línea de nodos) al de referencia: i
+    // sobre la línea de nodos y luego Ω sobre la normal del plano de referencia. Las
+    // fórmulas usuales tienen Z hacia el norte; aquí el norte es +Y
+    fn in_reference_plane(&self, x: f32, y: f32) -> Vec3 {
+        let (sin_node, cos_node) = self.longitude_of_ascending_node.sin_cos();
+        let (sin_inclination, cos_inclination) = self.inclination.sin_cos();
+
+        Vec3::new(
+            cos_node * x - sin_node * cos_inclination * y,
+            sin_inclination * y,
+            sin_node * x + cos_node * cos_inclination * y,
+        )
+    }
+}
+
+/// Resuelve la ecuación de Kepler `E - e sin E = M` con Newton-Raphson y devuelve la
+/// anomalía excéntrica E. Válido para órbitas cerradas (0 <= e < 1).
+pub fn solve_kepler(mean_anomaly: f32, eccentricity: f32) -> f32 {
+    let mean_anomaly = mean_anomaly.rem_euclid(2.0 * PI);
+    // Con excentricidades altas, partir de π converge aunque M esté cerca del periapsis
+    let mut eccentric_anomaly = if eccentricity < 0.8 { mean_anomaly } else { PI };
+
+    for _ in 0..50 {
+        let error = eccentric_anomaly - eccentricity * eccentric_anomaly.sin() - mean_anomaly;
+        let step = error / (1.0 - eccentricity * eccentric_anomaly.cos());
+        eccentric_anomaly -= step;
+        if step.abs() < 1e-6 {
+            break;
+        }
+    }
+
+    eccentric_anomaly
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: Vec3, expected: Vec3) {
+        assert!((actual - expected).norm() < 1e-4, "{:?} != {:?}", actual, expected);
+    }
+
+    #[test]
+    fn kepler_equation_matches_reference_solution() {
+        // Vallado, "Fundamentals of Astrodynamics", ejemplo 2-1: M = 235.4°, e = 0.4
+        let eccentric_anomaly = solve_kepler(235.4f32.to_radians(), 0.4);
+        assert!((eccentric_anomaly.to_degrees() - 220.512_07).abs() < 1e-3);
+
+        // Excentricidad alta, donde partir de E = M no converge bien
+        let eccentric_anomaly = solve_kepler(0.1, 0.95);
+        assert!((eccentric_anomaly - 0.95 * eccentric_anomaly.sin() - 0.1).abs() < 1e-5);
+    }
+
+    #[test]
+    fn circular_orbit_matches_flat_circle() {
+        let orbit = OrbitalElements::circular(5.0);
+        for step in 0..16 {
+            let angle = step as f32 * 0.4;
+            assert_close(orbit.position(angle), Vec3::new(5.0 * angle.cos(), 0.0, 5.0 * angle.sin()));
+        }
+    }
+
+    #[test]
+    fn eccentric_orbit_reaches_periapsis_and_apoapsis() {
+        let orbit = OrbitalElements::from_degrees(10.0, 0.5, 0.0, 0.0, 90.0, 0.0);
+
+        // El periapsis está en la dirección de ω (90°, sobre +Z) a a(1 - e)
+        assert_close(orbit.position(0.0), Vec3::new(0.0, 0.0, 5.0));
+        // Media órbita después, el apoapsis queda del lado opuesto a a(1 + e)
+        assert_close(orbit.position(PI), Vec3::new(0.0, 0.0, -15.0));
+        // A un cuarto del período el cuerpo avanzó más que un cuarto de vuelta
+        let quarter = orbit.position(PI / 2.0);
+        assert!(quarter.x < 0.0 && quarter.z < 0.0);
+    }
+
+    #[test]
+    fn inclined_orbit_rises_above_the_reference_plane() {
+        // Ω = 90°: la línea de nodos es el eje Z; con ω = 90° el periapsis está en lo alto
+        let orbit = OrbitalElements::from_degrees(2.0, 0.0, 30.0, 90.0, 90.0, 0.0);
+        let highest = orbit.position(0.0);
+
+        assert_close(highest, Vec3::new(-2.0 * 30f32.to_radians().cos(), 2.0 * 30f32.to_radians().sin(), 0.0));
+        // En el nodo ascendente cruza el plano de referencia
+        assert_close(orbit.position(-PI / 2.0), Vec3::new(0.0, 0.0, 2.0));
+    }
+
+    #[test]
+    fn velocity_follows_the_position() {
+        let orbit = OrbitalElements::from_degrees(10.0, 0.5, 20.0, 40.0, 60.0, 30.0);
+        let mu = 50.0;
+        // Con la gravedad de `mu`, la anomalía media avanza sqrt(mu / a³) por segundo
+        let mean_motion = (mu / 1000.0f32).sqrt();
+
+        for elapsed in [0.0, 1.0, 2.5] {
+            let h = 1e-3;
+            let numeric = (orbit.position(elapsed + h * mean_motion) - orbit.position(elapsed - h * mean_motion)) / (2.0 * h);
+            let velocity = orbit.velocity(elapsed, mu);
+            assert!((velocity - numeric).norm() < 1e-2 * velocity.norm(), "{:?} != {:?}", velocity, numeric);
+        }
+    }
+
+    #[test]
+    fn earth_position_at_perihelion() {
+        // Tierra (J2000): a = 1 UA, e = 0.0167, ϖ = Ω + ω = 102.94°. En el perihelio está a
+        // 0.9833 UA en dirección de la longitud del perihelio.
+        let orbit = OrbitalElements::from_degrees(1.0, 0.016_71, 0.0, -11.26, 114.2, 0.0);
+        let longitude = 102.94f32.to_radians();
+
+        assert_close(orbit.position(0.0), Vec3::new(longitude.cos(), 0.0, longitude.sin()) * 0.983_29);
+    }
+}
@@ -0,0 +1,294 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use crate::color::Color;
+
+/// Valor de un parámetro de shader.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ParamValue {
+    Float(f32),
+    Int(i32),
+    Color(Color),
+}
+
+impl fmt::Display for ParamValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParamValue::Float(value) => write!(f, "{:.3}", value),
+            ParamValue::Int(value) => write!(f, "{}", value),
+            ParamValue::Color(color) => write!(f, "#{:06X}", color.to_hex()),
+        }
+    }
+}
+
+/// Tipo, valor por defecto y rango de un parámetro.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ParamKind {
+    Float { default: f32, min: f32, max: f32 },
+    Int { default: i32, min: i32, max: i32 },
+    Color { default: Color },
+}
+
+/// Parámetro que un shader declara al registrarse.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ParamSpec {
+    pub name: &'static str,
+    pub kind: ParamKind,
+}
+
+impl ParamSpec {
+    pub const fn float(name: &'static str, default: f32, min: f32, max: f32) -> Self {
+        ParamSpec { name, kind: ParamKind::Float { default, min, max } }
+    }
+
+    pub const fn int(name: &'static str, default: i32, min: i32, max: i32) -> Self {
+        ParamSpec { name, kind: ParamKind::Int { default, min, max } }
+    }
+
+    pub const fn color(name: &'static str, default: Color) -> Self {
+        ParamSpec { name, kind: ParamKind::Color { default } }
+    }
+
+    pub fn default_value(&self) -> ParamValue {
+        match self.kind {
+            ParamKind::Float { default, .. } => ParamValue::Float(default),
+            ParamKind::Int { default, .. } => ParamValue::Int(default),
+            ParamKind::Color { default } => ParamValue::Color(default),
+        }
+    }
+
+    /// Ajusta un valor al tipo y rango del parámetro; `None` si el tipo no corresponde.
+    /// Los enteros se aceptan donde se espera un float.
+    pub fn accept(&self, value: ParamValue) -> Option<ParamValue> {
+        match (self.kind, value) {
+            (ParamKind::Float { min, max, .. }, ParamValue::Float(value)) => Some(ParamValue::Float(value.clamp(min, max))),
+            (ParamKind::Float { min, max, .. }, ParamValue::Int(value)) => Some(ParamValue::Float((value as f32).clamp(min, max))),
+            (ParamKind::Int { min, max, .. }, ParamValue::Int(value)) => Some(ParamValue::Int(value.clamp(min, max))),
+            (ParamKind::Color { .. }, ParamValue::Color(color)) => Some(ParamValue::Color(color)),
+            _ => None,
+        }
+    }
+
+    /// Valor siguiente (`direction` = 1) o anterior (-1) al ajustar desde el teclado:
+    /// los floats avanzan un 2% de su rango, los enteros de a uno y los colores se
+    /// aclaran u oscurecen.
+    pub fn step(&self, value: ParamValue, direction: i32) -> ParamValue {
+        let stepped = match (self.kind, value) {
+            (ParamKind::Float { min, max, .. }, ParamValue::Float(value)) => ParamValue::Float(value + (max - min) * 0.02 * direction as f32),
+            (ParamKind::Int { .. }, ParamValue::Int(value)) => ParamValue::Int(value + direction),
+            (ParamKind::Color { .. }, ParamValue::Color(color)) => {
+                let target = if direction > 0 { Color::from_hex(0xFFFFFF) } else { Color::black() };
+                ParamValue::Color(color.lerp(&target, 0.1))
+            }
+            _ => value,
+        };
+        self.accept(stepped).unwrap_or(value)
+    }
+}
+
+/// Valores de parámetros por nombre. Se usa tanto para los valores que fija cada objeto
+/// como para el juego completo que recibe el shader en los `Uniforms`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ShaderParams {
+    values: Vec<(String, ParamValue)>,
+}
+
+impl ShaderParams {
+    pub fn new() -> Self {
+        ShaderParams::default()
+    }
+
+    /// Valores por defecto de todos los parámetros, reemplazados por los de `overrides`
+    /// que correspondan a algún parámetro con el tipo correcto.
+    pub fn resolve(specs: &[ParamSpec], overrides: &ShaderParams) -> Self {
+        let values = specs
+            .iter()
+            .map(|spec| {
+                let value = overrides
+                    .get(spec.name)
+                    .and_then(|value| spec.accept(value))
+                    .unwrap_or_else(|| spec.default_value());
+                (spec.name.to_string(), value)
+            })
+            .collect();
+        ShaderParams { values }
+    }
+
+    pub fn get(&self, name: &str) -> Option<ParamValue> {
+        self.values.iter().find(|(existing, _)| existing == name).map(|&(_, value)| value)
+    }
+
+    pub fn set(&mut self, name: &str, value: ParamValue) {
+        match self.values.iter_mut().find(|(existing, _)| existing == name) {
+            Some(entry) => entry.1 = value,
+            None => self.values.push((name.to_string(), value)),
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, ParamValue)> {
+        self.values.iter().map(|(name, value)| (name.as_str(), *value))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    // Los shaders leen sus parámetros ya resueltos, así que uno que falte es un error
+    // del shader y no del usuario
+    pub fn float(&self, name: &str) -> f32 {
+        match self.get(name) {
+            Some(ParamValue::Float(value)) => value,
+            other => panic!("El parámetro {} no es un float: {:?}", name, other),
+        }
+    }
+
+    pub fn int(&self, name: &str) -> i32 {
+        match self.get(name) {
+            Some(ParamValue::Int(value)) => value,
+            other => panic!("El parámetro {} no es un entero: {:?}", name, other),
+        }
+    }
+
+    pub fn color(&self, name: &str) -> Color {
+        match self.get(name) {
+            Some(ParamValue::Color(color)) => color,
+            other => panic!("El parámetro {} no es un color: {:?}", name, other),
+        }
+    }
+}
+
+/// Parámetros por objeto, guardados en TOML con una tabla por objeto:
+///
+/// ```toml
+/// [Tierra]
+/// land_threshold = 0.45
+/// ocean_color = "#006994"
+/// ```
+///
+/// Los colores se escriben como `"#RRGGBB"`.
+pub fn load_params(path: &Path) -> io::Result<Vec<(String, ShaderParams)>> {
+    parse_params(&fs::read_to_string(path)?)
+}
+
+pub fn parse_params(source: &str) -> io::Result<Vec<(String, ShaderParams)>> {
+    let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+    let table: toml::Table = source.parse().map_err(|error: toml::de::Error| invalid(error.to_string()))?;
+
+    table
+        .into_iter()
+        .map(|(object, entries)| {
+            let toml::Value::Table(entries) = entries else {
+                return Err(invalid(format!("{} debe ser una tabla de parámetros", object)));
+            };
+            let params = params_from_table(&entries).map_err(|message| invalid(format!("{}.{}", object, message)))?;
+            Ok((object, params))
+        })
+        .collect()
+}
+
+// Valores de la tabla de un objeto; el error empieza con el nombre del parámetro
+pub(crate) fn params_from_table(entries: &toml::Table) -> Result<ShaderParams, String> {
+    let mut params = ShaderParams::new();
+    for (name, value) in entries {
+        let value = match value {
+            toml::Value::Float(value) => ParamValue::Float(*value as f32),
+            toml::Value::Integer(value) => ParamValue::Int(i32::try_from(*value).map_err(|_| format!("{}: entero fuera de rango", name))?),
+            toml::Value::String(text) => ParamValue::Color(parse_color(text).ok_or_else(|| format!("{}: color inválido {:?}", name, text))?),
+            other => return Err(format!("{}: valor no soportado {}", name, other)),
+        };
+        params.set(name, value);
+    }
+    Ok(params)
+}
+
+/// Escribe los parámetros en el mismo formato que lee [`load_params`].
+pub fn save_params<'a, I>(path: &Path, objects: I) -> io::Result<()>
+where
+    I: IntoIterator<Item = (&'a str, &'a ShaderParams)>,
+{
+    let mut table = toml::Table::new();
+    for (object, params) in objects {
+        if params.is_empty() {
+            continue;
+        }
+        let entries = params
+            .iter()
+            .map(|(name, value)| {
+                let value = match value {
+                    ParamValue::Float(value) => toml::Value::Float(value as f64),
+                    ParamValue::Int(value) => toml::Value::Integer(value as i64),
+                    ParamValue::Color(color) => toml::Value::String(format!("#{:06X}", color.to_hex())),
+                };
+                (name.to_string(), value)
+            })
+            .collect();
+        table.insert(object.to_string(), toml::Value::Table(entries));
+    }
+
+    fs::write(path, table.to_string())
+}
+
+pub(crate) fn parse_color(text: &str) -> Option<Color> {
+    let hex = text.strip_prefix('#')?;
+    if hex.len() != 6 {
+        return None;
+    }
+    u32::from_str_radix(hex, 16).ok().map(Color::from_hex)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SPECS: &[ParamSpec] = &[
+        ParamSpec::float("zoom", 50.0, 1.0, 200.0),
+        ParamSpec::int("grid", 3, 0, 10),
+        ParamSpec::color("base_color", Color::new(255, 69, 0)),
+    ];
+
+    #[test]
+    fn resolve_uses_defaults_and_clamps_overrides() {
+        let mut overrides = ShaderParams::new();
+        overrides.set("zoom", ParamValue::Int(500));
+        overrides.set("grid", ParamValue::Float(2.0)); // Tipo incorrecto, se ignora
+        overrides.set("unknown", ParamValue::Int(1));
+
+        let params = ShaderParams::resolve(SPECS, &overrides);
+
+        assert_eq!(params.float("zoom"), 200.0);
+        assert_eq!(params.int("grid"), 3);
+        assert_eq!(params.color("base_color"), Color::new(255, 69, 0));
+        assert_eq!(params.get("unknown"), None);
+    }
+
+    #[test]
+    fn params_round_trip_through_toml() {
+        let source = "[Tierra]\nland_threshold = 0.45\ngrid = 4\nocean_color = \"#006994\"\n";
+        let objects = parse_params(source).unwrap();
+
+        assert_eq!(objects.len(), 1);
+        let (name, params) = &objects[0];
+        assert_eq!(name, "Tierra");
+        assert_eq!(params.get("land_threshold"), Some(ParamValue::Float(0.45)));
+        assert_eq!(params.get("grid"), Some(ParamValue::Int(4)));
+        assert_eq!(params.get("ocean_color"), Some(ParamValue::Color(Color::from_hex(0x006994))));
+
+        let path = std::env::temp_dir().join(format!("proyecto3_gc_params_round_trip_{}.toml", std::process::id()));
+        save_params(&path, objects.iter().map(|(name, params)| (name.as_str(), params))).unwrap();
+        assert_eq!(load_params(&path).unwrap(), objects);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn invalid_colors_are_reported() {
+        let error = parse_params("[Sol]\nbase_color = \"rojo\"\n").unwrap_err();
+        assert!(error.to_string().contains("Sol.base_color"));
+    }
+
+    #[test]
+    fn out_of_range_integers_are_reported() {
+        let error = parse_params("[Saturno]\ngrid = 4294967297\n").unwrap_err();
+        assert!(error.to_string().contains("Saturno.grid: entero fuera de rango"), "{}", error);
+    }
+}
@@ -1,14 +1,29 @@
-use nalgebra_glm::Vec3;
+use nalgebra_glm::{Vec3, Mat4};
+use crate::orbit::OrbitalElements;
+use crate::params::ShaderParams;
+use crate::shaders::DEFAULT_VERTEX_SHADER;
+use crate::transform::create_model_matrix;
+use crate::triangle::RasterState;
 
+/// Cuerpo que orbita alrededor de su nodo padre en el grafo de escena (o del origen si
+/// no tiene) con su propio shader.
 pub struct Planet {
     pub name: String,
     pub radius: f32,
-    pub orbit_radius: f32,
-    pub orbit_speed: f32,
-    pub rotation_speed: f32,
+    pub orbit: OrbitalElements,
+    pub orbit_speed: f32, // Radianes de anomalía media por segundo
+    pub rotation_speed: f32, // Radianes por segundo alrededor de su propio eje
+    pub axial_tilt: f32,     // Inclinación del eje de rotación respecto a la órbita, en radianes
+    pub rotation_phase: f32, // Ángulo de rotación inicial, en radianes
     pub color: u32,
-    pub current_angle: f32,
-    pub shader_index: u32, // Nuevo campo para el índice del shader
+    pub mass: f32, // Solo cuenta en el modo de N cuerpos
+    pub free_position: Option<Vec3>, // Posición que fija la simulación de N cuerpos en lugar de la órbita
+    pub current_angle: f32, // Anomalía media recorrida desde la época
+    pub rotation_angle: f32, // Giro acumulado desde el inicio, sin la fase
+    pub shader: String, // Nombre del fragment shader en el registro
+    pub vertex_shader: String,
+    pub shader_params: ShaderParams, // Solo los valores que cambian respecto al shader
+    pub raster_state: RasterState, // Culling de caras traseras por defecto
 }
 
 impl Planet {
@@ -19,32 +34,98 @@ impl Planet {
         orbit_speed: f32,
         rotation_speed: f32,
         color: u32,
-        shader_index: u32, // Nuevo parámetro
+        shader: &str,
     ) -> Self {
         Planet {
             name: name.to_string(),
             radius,
-            orbit_radius,
+            orbit: OrbitalElements::circular(orbit_radius),
             orbit_speed,
             rotation_speed,
             color,
+            mass: 0.0,
+            free_position: None,
+            axial_tilt: 0.0,
+            rotation_phase: 0.0,
             current_angle: 0.0,
-            shader_index, // Inicializa el índice del shader
+            rotation_angle: 0.0,
+            shader: shader.to_string(),
+            vertex_shader: DEFAULT_VERTEX_SHADER.to_string(),
+            shader_params: ShaderParams::new(),
+            raster_state: RasterState::default(),
         }
     }
 
-    pub fn update_position(&mut self) {
-        self.current_angle += self.orbit_speed;
-        if self.current_angle > 2.0 * std::f32::consts::PI {
-            self.current_angle -= 2.0 * std::f32::consts::PI;
-        }
+    /// Avanza la órbita `dt` segundos; con `dt` negativo retrocede.
+    pub fn update_position(&mut self, dt: f32) {
+        self.current_angle = (self.current_angle + self.orbit_speed * dt).rem_euclid(2.0 * std::f32::consts::PI);
+    }
+
+    pub fn update_rotation(&mut self, dt: f32) {
+        self.rotation_angle = (self.rotation_angle + self.rotation_speed * dt).rem_euclid(2.0 * std::f32::consts::PI);
+    }
+
+    /// Reemplaza la órbita circular de `new` por una kepleriana.
+    pub fn with_orbit(mut self, orbit: OrbitalElements) -> Self {
+        self.orbit = orbit;
+        self
+    }
+
+    /// Fija la inclinación del eje y la fase de rotación, en grados.
+    pub fn with_axis(mut self, axial_tilt_degrees: f32, rotation_phase_degrees: f32) -> Self {
+        self.axial_tilt = axial_tilt_degrees.to_radians();
+        self.rotation_phase = rotation_phase_degrees.to_radians();
+        self
+    }
+
+    /// Matriz de modelo: gira sobre su eje Y local, inclina ese eje hacia +X y traslada a
+    /// su posición en la órbita. Lo dibujado en espacio del objeto gira con el planeta.
+    pub fn get_model_matrix(&self) -> Mat4 {
+        create_model_matrix(self.get_position(), self.radius, self.get_rotation())
+    }
+
+    /// Ángulos de rotación para `create_model_matrix`: el giro diario y la inclinación.
+    pub fn get_rotation(&self) -> Vec3 {
+        Vec3::new(0.0, self.rotation_phase + self.rotation_angle, -self.axial_tilt)
     }
 
     pub fn get_position(&self) -> Vec3 {
-        Vec3::new(
-            self.orbit_radius * self.current_angle.cos(),
-            0.0,
-            self.orbit_radius * self.current_angle.sin(),
-        )
+        self.free_position.unwrap_or_else(|| self.orbit.position(self.current_angle))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nalgebra_glm::{Vec4, vec4_to_vec3};
+
+    fn transform(matrix: &Mat4, point: Vec3) -> Vec3 {
+        vec4_to_vec3(&(matrix * Vec4::new(point.x, point.y, point.z, 1.0)))
+    }
+
+    #[test]
+    fn spin_keeps_the_tilted_axis_fixed() {
+        let mut planet = Planet::new("Tierra", 1.0, 0.0, 0.0, 0.3, 0x0077be, "earth_clouds").with_axis(23.4, 0.0);
+        let pole = transform(&planet.get_model_matrix(), Vec3::y());
+        let equator = transform(&planet.get_model_matrix(), Vec3::x());
+
+        for _ in 0..5 {
+            planet.update_rotation(1.0);
+        }
+
+        // El polo no se mueve al girar, pero los puntos del ecuador sí
+        assert!((transform(&planet.get_model_matrix(), Vec3::y()) - pole).norm() < 1e-5);
+        assert!((transform(&planet.get_model_matrix(), Vec3::x()) - equator).norm() > 0.5);
+        // El eje queda inclinado 23.4° respecto a la normal de la órbita
+        assert!((pole.y.acos().to_degrees() - 23.4).abs() < 1e-3);
+    }
+
+    #[test]
+    fn rotation_phase_offsets_the_spin() {
+        let planet = Planet::new("Marte", 1.0, 0.0, 0.0, 0.0, 0xd95d39, "cellular").with_axis(0.0, 90.0);
+        let point = transform(&planet.get_model_matrix(), Vec3::x());
+
+        // Girar 90° sobre Y lleva +X a -Z
+        assert!((point - Vec3::new(0.0, 0.0, -1.0)).norm() < 1e-5);
     }
-}
\ No newline at end of file
+}
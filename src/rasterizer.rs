@@ -0,0 +1,196 @@
+use rayon::prelude::*;
+use crate::fragment::Fragment;
+use crate::framebuffer::Framebuffer;
+use crate::triangle::{triangle, Bounds, RasterState};
+use crate::vertex::Vertex;
+
+/// Lado en píxeles de cada tile de pantalla
+pub const TILE_SIZE: usize = 64;
+
+/// Contadores de trabajo del rasterizador, útiles para medir el overdraw
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RenderStats {
+    pub triangles: u64,      // Triángulos enviados al rasterizador (después de recorte y culling)
+    pub covered: u64,        // Píxeles cubiertos por algún triángulo
+    pub depth_rejected: u64, // Píxeles descartados por la prueba de profundidad temprana
+    pub shaded: u64,         // Fragmentos que llegaron al fragment shader
+}
+
+impl std::ops::AddAssign for RenderStats {
+    fn add_assign(&mut self, other: RenderStats) {
+        self.triangles += other.triangles;
+        self.covered += other.covered;
+        self.depth_rejected += other.depth_rejected;
+        self.shaded += other.shaded;
+    }
+}
+
+/// Rasteriza triángulos ya proyectados a pantalla. Cada triángulo se asigna a los tiles
+/// que toca su caja envolvente, y luego cada tile se rasteriza, sombrea y prueba
+/// profundidad en paralelo sobre su propia porción del framebuffer.
+/// Dentro de un tile los triángulos se procesan en el orden original, así que el
+/// resultado es el mismo que el de un rasterizador secuencial.
+pub fn rasterize<F>(framebuffer: &mut Framebuffer, triangles: &[[Vertex; 3]], state: &RasterState, shade: F) -> RenderStats
+where
+    F: Fn(&Fragment) -> Option<u32> + Sync,
+{
+    rasterize_tiles(framebuffer, triangles, state, TILE_SIZE, shade)
+}
+
+fn rasterize_tiles<F>(framebuffer: &mut Framebuffer, triangles: &[[Vertex; 3]], state: &RasterState, tile_size: usize, shade: F) -> RenderStats
+where
+    F: Fn(&Fragment) -> Option<u32> + Sync,
+{
+    let tiles_x = framebuffer.width.div_ceil(tile_size);
+    let tiles_y = framebuffer.height.div_ceil(tile_size);
+    let screen = Bounds::new(0, 0, framebuffer.width as i32, framebuffer.height as i32);
+
+    let bins = bin_triangles(triangles, &screen, tile_size, tiles_x, tiles_y);
+
+    let mut stats = framebuffer
+        .tiles_mut(tile_size)
+        .into_par_iter()
+        .zip(bins.par_iter())
+        .filter(|(_, bin)| !bin.is_empty())
+        .map(|(mut tile, bin)| {
+            let mut tile_stats = RenderStats::default();
+
+            for &index in bin {
+                let [v1, v2, v3] = &triangles[index];
+                triangle(v1, v2, v3, state, &mut tile, &mut tile_stats, &shade);
+            }
+
+            tile_stats
+        })
+        .reduce(RenderStats::default, |mut total, tile_stats| {
+            total += tile_stats;
+            total
+        });
+
+    stats.triangles = triangles.len() as u64;
+    stats
+}
+
+// Lista, por tile, los índices de los triángulos cuya caja envolvente lo toca
+fn bin_triangles(triangles: &[[Vertex; 3]], screen: &Bounds, tile_size: usize, tiles_x: usize, tiles_y: usize) -> Vec<Vec<usize>> {
+    let mut bins = vec![Vec::new(); tiles_x * tiles_y];
+
+    for (index, [v1, v2, v3]) in triangles.iter().enumerate() {
+        let area = screen.triangle_bounds(v1, v2, v3);
+        if area.is_empty() {
+            continue;
+        }
+
+        let (first_tx, last_tx) = (area.min_x as usize / tile_size, (area.max_x - 1) as usize / tile_size);
+        let (first_ty, last_ty) = (area.min_y as usize / tile_size, (area.max_y - 1) as usize / tile_size);
+
+        for ty in first_ty..=last_ty {
+            for tx in first_tx..=last_tx {
+                bins[ty * tiles_x + tx].push(index);
+            }
+        }
+    }
+
+    bins
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nalgebra_glm::{Vec2, Vec3};
+    use crate::color::Color;
+    use crate::triangle::{CullMode, FrontFace};
+
+    fn screen_vertex(x: f32, y: f32, depth: f32, color: Color) -> Vertex {
+        let mut vertex = Vertex::new(Vec3::new(x, y, depth), Vec3::z(), Vec2::zeros());
+        vertex.color = color;
+        vertex
+    }
+
+    #[test]
+    fn triangles_are_binned_into_the_tiles_they_touch() {
+        let screen = Bounds::new(0, 0, 256, 256);
+        // Cruza el borde vertical entre los dos primeros tiles de la fila de arriba
+        let straddling = [
+            screen_vertex(50.0, 10.0, 0.5, Color::black()),
+            screen_vertex(80.0, 20.0, 0.5, Color::black()),
+            screen_vertex(55.0, 30.0, 0.5, Color::black()),
+        ];
+        let bins = bin_triangles(&[straddling], &screen, 64, 4, 4);
+
+        let touched: Vec<usize> = (0..bins.len()).filter(|&tile| !bins[tile].is_empty()).collect();
+        assert_eq!(touched, vec![0, 1]);
+        assert_eq!(bins[0], vec![0]);
+    }
+
+    #[test]
+    fn output_does_not_depend_on_tile_size() {
+        let (red, green, blue) = (Color::new(255, 0, 0), Color::new(0, 255, 0), Color::new(0, 0, 255));
+        // Triángulos que se cruzan en profundidad y cubren varios tiles
+        let triangles = [
+            [screen_vertex(5.0, 5.0, 0.2, red), screen_vertex(150.0, 40.0, 0.8, red), screen_vertex(30.0, 120.0, 0.5, green)],
+            [screen_vertex(20.0, 100.0, 0.9, blue), screen_vertex(140.0, 10.0, 0.1, blue), screen_vertex(130.0, 130.0, 0.4, green)],
+            [screen_vertex(70.5, 70.5, 0.3, green), screen_vertex(100.5, 20.5, 0.3, red), screen_vertex(110.5, 90.5, 0.3, blue)],
+        ];
+        let state = RasterState::new(CullMode::None, FrontFace::CounterClockwise);
+
+        let render = |tile_size| {
+            let mut framebuffer = Framebuffer::new(160, 140);
+            framebuffer.clear();
+            let stats = rasterize_tiles(&mut framebuffer, &triangles, &state, tile_size, |fragment| Some(fragment.color.to_hex()));
+            (framebuffer.get_active_buffer().to_vec(), framebuffer.zbuffer.clone(), stats)
+        };
+
+        let reference = render(160);
+        assert!(reference.0.iter().any(|&pixel| pixel != 0));
+        for tile_size in [8, 13, 64] {
+            assert!(render(tile_size) == reference, "tiles de {}", tile_size);
+        }
+    }
+
+    #[test]
+    fn early_z_skips_shading_hidden_fragments() {
+        let quad = |depth: f32, color: Color| {
+            let corners = [(10.0, 10.0), (60.0, 10.0), (60.0, 60.0), (10.0, 60.0)].map(|(x, y)| screen_vertex(x, y, depth, color));
+            [[corners[0].clone(), corners[1].clone(), corners[2].clone()], [corners[0].clone(), corners[2].clone(), corners[3].clone()]]
+        };
+        // El cercano primero: el lejano queda entero detrás
+        let triangles: Vec<[Vertex; 3]> = quad(0.2, Color::new(255, 0, 0)).into_iter().chain(quad(0.8, Color::new(0, 0, 255))).collect();
+        let state = RasterState::new(CullMode::None, FrontFace::CounterClockwise);
+        let mut framebuffer = Framebuffer::new(64, 64);
+        framebuffer.clear();
+
+        let stats = rasterize(&mut framebuffer, &triangles, &state, |fragment| Some(fragment.color.to_hex()));
+
+        assert_eq!(stats.covered, 2 * 50 * 50);
+        assert_eq!(stats.shaded, 50 * 50);
+        assert_eq!(stats.depth_rejected, 50 * 50);
+        assert!(framebuffer.get_active_buffer().iter().all(|&pixel| pixel == 0 || pixel == 0xFF0000));
+    }
+
+    #[test]
+    fn discarded_fragments_leave_what_is_behind() {
+        let quad = |depth: f32, color: Color| {
+            let corners = [(10.0, 10.0), (60.0, 10.0), (60.0, 60.0), (10.0, 60.0)].map(|(x, y)| screen_vertex(x, y, depth, color));
+            [[corners[0].clone(), corners[1].clone(), corners[2].clone()], [corners[0].clone(), corners[2].clone(), corners[3].clone()]]
+        };
+        // Un quad negro adelante, con su mitad izquierda descartada, y uno azul detrás
+        let triangles: Vec<[Vertex; 3]> = quad(0.2, Color::black()).into_iter().chain(quad(0.8, Color::new(0, 0, 255))).collect();
+        let state = RasterState::new(CullMode::None, FrontFace::CounterClockwise);
+        let mut framebuffer = Framebuffer::new(64, 64);
+        framebuffer.set_background_color(0xFFFFFF);
+        framebuffer.clear();
+
+        rasterize(&mut framebuffer, &triangles, &state, |fragment| {
+            let discarded = fragment.color == Color::black() && fragment.position.x < 35.0;
+            (!discarded).then(|| fragment.color.to_hex())
+        });
+
+        // El negro se escribe como cualquier color y tapa lo de atrás
+        let row = &framebuffer.get_active_buffer()[30 * 64..31 * 64];
+        assert_eq!(row[20], 0x0000FF);
+        assert_eq!(row[40], 0x000000);
+        assert!((framebuffer.zbuffer[30 * 64 + 20] - 0.8).abs() < 1e-5);
+        assert!((framebuffer.zbuffer[30 * 64 + 40] - 0.2).abs() < 1e-5);
+    }
+}
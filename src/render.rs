@@ -0,0 +1,93 @@
+use nalgebra_glm::{Mat4, Vec3};
+use rayon::prelude::*;
+use std::sync::Arc;
+use crate::clipping::{clip_triangle, to_screen};
+use crate::color::Color;
+use crate::fragment::Fragment;
+use crate::framebuffer::Framebuffer;
+use crate::light::{shade, Light, Surface};
+use crate::maps::SurfaceMaps;
+use crate::material::Material;
+use crate::noise::SurfaceNoise;
+use crate::params::ShaderParams;
+use crate::rasterizer::{rasterize, RenderStats};
+use crate::shaders::{FragmentShader, VertexShader};
+use crate::shadow::ShadowMap;
+use crate::texture::Texture;
+use crate::triangle::{is_culled, RasterState};
+use crate::vertex::Vertex;
+
+/// Datos constantes durante el dibujo de un objeto, compartidos por sus shaders.
+pub struct Uniforms {
+    pub model_matrix: Mat4,
+    pub view_matrix: Mat4,
+    pub projection_matrix: Mat4,
+    pub viewport_matrix: Mat4,
+    pub time: f32, // Segundos simulados
+    pub noise: SurfaceNoise,
+    pub params: ShaderParams, // Parámetros ya resueltos del fragment shader
+    pub lights: Vec<Light>,
+    pub camera_position: Vec3, // Para el brillo especular
+    pub shadow: Option<Arc<ShadowMap>>, // Sombras de la luz puntual donde se dibujó el mapa
+    pub texture: Option<Arc<Texture>>, // Imagen del objeto, si tiene una
+    pub maps: SurfaceMaps, // Normales, relieve y brillo del objeto
+    pub material: Option<Arc<Material>>, // Del .mtl, para la parte del modelo que se dibuja
+}
+
+impl Uniforms {
+    /// Ilumina el color que calculó un fragment shader con las luces del objeto y su
+    /// sombra (ver [`crate::light::shade`]), con la normal y el brillo de sus mapas.
+    pub fn illuminate(&self, fragment: &Fragment, albedo: Color, surface: Surface) -> Color {
+        self.illuminate_normal(fragment, self.maps.perturb_normal(fragment), albedo, surface)
+    }
+
+    /// Como [`Uniforms::illuminate`], pero con una normal que calculó el shader (por
+    /// ejemplo con [`crate::maps::relief_normal`]) en lugar de la de los mapas.
+    pub fn illuminate_normal(&self, fragment: &Fragment, normal: Vec3, albedo: Color, surface: Surface) -> Color {
+        let surface = self.maps.surface(fragment, surface);
+        shade(&self.lights, albedo, fragment.world_position, normal, self.camera_position, surface, self.shadow.as_deref())
+    }
+
+    /// Color de la textura del objeto en las coordenadas del fragmento, o `None` si el
+    /// objeto no tiene textura.
+    pub fn sample_texture(&self, fragment: &Fragment) -> Option<Color> {
+        self.texture.as_ref().map(|texture| texture.sample(fragment.tex_coords, fragment.tex_coords_dx, fragment.tex_coords_dy))
+    }
+
+    /// Fracción de la luz del mapa de sombras que llega al fragmento (1 sin mapa), para
+    /// los shaders que iluminan por su cuenta.
+    pub fn shadow_visibility(&self, fragment: &Fragment) -> f32 {
+        self.shadow.as_ref().map_or(1.0, |shadow| shadow.visibility(fragment.world_position, fragment.normal))
+    }
+}
+
+/// Dibuja una lista de triángulos (de a tres vértices) con los shaders indicados.
+///
+/// Recorre el pipeline completo: vertex shader, recorte, proyección a pantalla,
+/// culling y rasterizado por tiles. El framebuffer debe estar limpio y, con MSAA,
+/// hay que llamar a [`Framebuffer::resolve`] al terminar el frame.
+pub fn render(
+    framebuffer: &mut Framebuffer,
+    uniforms: &Uniforms,
+    vertex_array: &[Vertex],
+    vertex_shader: &dyn VertexShader,
+    fragment_shader: &dyn FragmentShader,
+    raster_state: &RasterState,
+) -> RenderStats {
+    let transformed_vertices: Vec<_> = vertex_array.par_iter()
+        .map(|vertex| vertex_shader.shade(vertex, uniforms))
+        .collect();
+
+    // Recorte en espacio homogéneo antes de la división por w, para que los vértices
+    // detrás de la cámara no se proyecten invertidos en la pantalla
+    let triangles: Vec<_> = transformed_vertices.par_chunks(3)
+        .filter(|chunk| chunk.len() == 3)
+        .flat_map_iter(|chunk| clip_triangle(&chunk[0], &chunk[1], &chunk[2]))
+        .map(|tri| tri.map(|vertex| to_screen(&vertex, &uniforms.viewport_matrix)))
+        .filter(|tri| !is_culled(&tri[0], &tri[1], &tri[2], raster_state))
+        .collect();
+
+    rasterize(framebuffer, &triangles, raster_state, |fragment| {
+        fragment_shader.shade(fragment, uniforms).map(|color| color.to_hex())
+    })
+}
@@ -0,0 +1,68 @@
+use nalgebra_glm::{Vec2, Vec3, Mat4};
+use std::f32::consts::PI;
+use crate::params::ShaderParams;
+use crate::shaders::DEFAULT_VERTEX_SHADER;
+use crate::transform::create_model_matrix;
+use crate::triangle::{CullMode, FrontFace, RasterState};
+use crate::vertex::Vertex;
+
+/// Anillo plano alrededor de su nodo padre, en el plano ecuatorial inclinado `axial_tilt`.
+pub struct Ring {
+    pub name: String,
+    pub outer_radius: f32,
+    pub inner_radius: f32,
+    pub axial_tilt: f32, // En radianes; normalmente la misma inclinación que el planeta
+    pub shader: String,
+    pub vertex_shader: String,
+    pub shader_params: ShaderParams,
+    pub raster_state: RasterState,
+}
+
+impl Ring {
+    pub fn new(name: &str, inner_radius: f32, outer_radius: f32, axial_tilt_degrees: f32, shader: &str) -> Self {
+        Ring {
+            name: name.to_string(),
+            outer_radius,
+            inner_radius,
+            axial_tilt: axial_tilt_degrees.to_radians(),
+            shader: shader.to_string(),
+            vertex_shader: DEFAULT_VERTEX_SHADER.to_string(),
+            shader_params: ShaderParams::new(),
+            // Se ve desde arriba y desde abajo
+            raster_state: RasterState::new(CullMode::None, FrontFace::CounterClockwise),
+        }
+    }
+
+    /// El mesh tiene radio exterior 1, así que la escala es el radio exterior.
+    pub fn get_model_matrix(&self) -> Mat4 {
+        create_model_matrix(Vec3::zeros(), self.outer_radius, Vec3::new(0.0, 0.0, -self.axial_tilt))
+    }
+
+    /// Mesh de este anillo, con `segments` divisiones alrededor.
+    pub fn mesh(&self, segments: usize) -> Vec<Vertex> {
+        ring_mesh(self.inner_radius / self.outer_radius, segments)
+    }
+}
+
+/// Corona circular en el plano XZ con radio exterior 1 y normal +Y. La coordenada de
+/// textura u va de 0 en el borde interior a 1 en el exterior, y v alrededor del anillo.
+pub fn ring_mesh(inner_ratio: f32, segments: usize) -> Vec<Vertex> {
+    let point = |radius: f32, step: usize| {
+        let angle = 2.0 * PI * step as f32 / segments as f32;
+        let u = (radius - inner_ratio) / (1.0 - inner_ratio);
+        Vertex::new(
+            Vec3::new(radius * angle.cos(), 0.0, radius * angle.sin()),
+            Vec3::y(),
+            Vec2::new(u, step as f32 / segments as f32),
+        )
+    };
+
+    (0..segments)
+        .flat_map(|step| {
+            let (inner, outer) = (point(inner_ratio, step), point(1.0, step));
+            let (next_inner, next_outer) = (point(inner_ratio, step + 1), point(1.0, step + 1));
+            // Antihorario visto desde +Y
+            [inner.clone(), next_outer.clone(), outer, inner, next_inner, next_outer]
+        })
+        .collect()
+}
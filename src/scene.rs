@@ -0,0 +1,657 @@
+use nalgebra_glm::Vec3;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::ops::Range;
+use toml::Spanned;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use crate::camera::Camera;
+use crate::clock::SimClock;
+use crate::framebuffer::Framebuffer;
+use crate::color::Color;
+use crate::graph::{Body, Node, NodeId, SceneGraph};
+use crate::light::Light;
+use crate::maps::SurfaceMaps;
+use crate::nbody::{Integrator, NBody, Particle};
+use crate::obj::{MeshPart, Obj};
+use crate::params::{ParamSpec, ShaderParams};
+use crate::planet::Planet;
+use crate::rasterizer::RenderStats;
+use crate::ring::Ring;
+use crate::triangle::{Interpolation, RasterState};
+use crate::noise::SurfaceNoise;
+use crate::render::{render, Uniforms};
+use crate::scene_file::{LightKind, MapsDescription, PhysicsDescription, SceneFile, ShadowDescription, TextureDescription};
+use crate::shaders::{FragmentShader, ShaderRegistry, VertexShader, DEFAULT_VERTEX_SHADER};
+use crate::shadow::ShadowMap;
+use crate::spaceship::Spaceship;
+use crate::texture::{Filter, Texture, Wrap};
+use crate::transform::{create_perspective_matrix, create_view_matrix, create_viewport_matrix};
+
+// Modelo de los cuerpos que no indican otro en el archivo de escena
+const DEFAULT_PLANET_MODEL: &str = "assets/models/sphere.obj";
+
+// En el modo de N cuerpos las flechas empujan la nave: cambio de velocidad por unidad
+// de `direction` en `move_spaceship`
+const SHIP_THRUST: f32 = 0.2;
+
+// Simulación de N cuerpos en curso
+struct Gravity {
+    system: NBody,
+    nodes: Vec<NodeId>, // Nodo de cada partícula
+    parents: Vec<(NodeId, Option<NodeId>)>, // Para volver a las órbitas al desactivarla
+}
+
+/// Luz de la escena. Una luz puntual sobre un nodo (`body`) lo acompaña: su posición
+/// es la del nodo en cada frame.
+#[derive(Clone, Copy, Debug)]
+pub struct SceneLight {
+    pub light: Light,
+    pub body: Option<NodeId>,
+}
+
+/// Estado completo del sistema solar: cuerpos, nave, cámara y fondo de estrellas
+pub struct Scene {
+    pub camera: Camera,
+    pub graph: SceneGraph,
+    pub spaceship: NodeId,
+    pub background: u32, // Color con el que hay que limpiar el framebuffer
+    pub star_color: u32,
+    pub stars: Vec<(usize, usize)>,
+    pub lights: Vec<SceneLight>,
+    pub clock: SimClock,
+    pub interpolation: Interpolation,
+    pub shaders: ShaderRegistry,
+    pub physics: PhysicsDescription, // Ajustes para la próxima vez que se active la gravedad
+    pub shadows: ShadowDescription,
+    gravity: Option<Gravity>,
+    meshes: Vec<Vec<MeshPart>>, // Las partes de cada modelo, por material
+}
+
+impl Scene {
+    /// El sistema solar de la escena por defecto (`assets/scenes/solar_system.toml`).
+    /// `seed` fija la posición de las estrellas; el resto de la escena ya es determinista
+    pub fn solar_system(width: usize, height: usize, seed: u64) -> Self {
+        Scene::from_file(&SceneFile::default(), ShaderRegistry::with_builtin(), width, height, seed)
+            .expect("No se pudo armar la escena por defecto")
+    }
+
+    /// Arma la escena descrita en el archivo. Los shaders se buscan en `shaders`, así
+    /// que el archivo puede usar shaders propios registrados antes. Un shader sin
+    /// registrar o un modelo que no se puede leer se informan con su línea.
+    pub fn from_file(file: &SceneFile, shaders: ShaderRegistry, width: usize, height: usize, seed: u64) -> io::Result<Self> {
+        let check_shaders = |fragment: &Spanned<String>, vertex: Option<&Spanned<String>>| {
+            if shaders.fragment(fragment.get_ref()).is_none() {
+                return Err(file.error_at(fragment.span(), &format!("fragment shader no registrado: {}", fragment.get_ref())));
+            }
+            match vertex {
+                Some(vertex) if shaders.vertex(vertex.get_ref()).is_none() => {
+                    Err(file.error_at(vertex.span(), &format!("vertex shader no registrado: {}", vertex.get_ref())))
+                }
+                Some(vertex) => Ok(vertex.get_ref().clone()),
+                None => Ok(DEFAULT_VERTEX_SHADER.to_string()),
+            }
+        };
+        let load_model = |path: &Path, span: Range<usize>| {
+            Obj::load(path).map_err(|error| file.error_at(span, &format!("no se pudo cargar {}: {}", path.display(), error)))
+        };
+
+        // Los objetos con la misma imagen y el mismo muestreo comparten la textura
+        let mut textures: Vec<((PathBuf, Filter, Wrap), Arc<Texture>)> = Vec::new();
+        let mut load_texture = |description: Option<&TextureDescription>| -> io::Result<Option<Arc<Texture>>> {
+            let Some(description) = description else {
+                return Ok(None);
+            };
+            let path = file.asset_path(description.path.get_ref());
+            let key = (path.clone(), description.filter, description.wrap);
+            if let Some((_, texture)) = textures.iter().find(|(loaded, _)| *loaded == key) {
+                return Ok(Some(texture.clone()));
+            }
+            let texture = Texture::load(&path)
+                .map_err(|error| file.error_at(description.path.span(), &format!("no se pudo cargar {}: {}", path.display(), error)))?;
+            let texture = Arc::new(texture.with_sampling(description.filter, description.wrap));
+            textures.push((key, texture.clone()));
+            Ok(Some(texture))
+        };
+
+        let mut graph = SceneGraph::new();
+        let mut meshes = Vec::new();
+        let mut mesh_paths: Vec<PathBuf> = Vec::new();
+
+        for description in &file.bodies {
+            let vertex_shader = check_shaders(&description.shader, description.vertex_shader.as_ref())?;
+            // Los cuerpos con el mismo modelo comparten el mesh
+            let (model, span) = match &description.model {
+                Some(model) => (file.asset_path(model.get_ref()), model.span()),
+                None => (PathBuf::from(DEFAULT_PLANET_MODEL), description.name.span()),
+            };
+            let mesh = match mesh_paths.iter().position(|path| *path == model) {
+                Some(mesh) => mesh,
+                None => {
+                    meshes.push(load_model(&model, span)?.get_mesh_parts());
+                    mesh_paths.push(model);
+                    meshes.len() - 1
+                }
+            };
+
+            let mut planet = Planet::new(
+                description.name.get_ref(),
+                description.radius,
+                0.0,
+                description.orbit_speed,
+                description.rotation_speed,
+                description.color,
+                description.shader.get_ref(),
+            )
+            .with_axis(description.axial_tilt, description.rotation_phase);
+            if let Some(orbit) = description.orbit {
+                planet = planet.with_orbit(orbit.into());
+            }
+            planet.vertex_shader = vertex_shader;
+            planet.shader_params = description.params.clone();
+            planet.mass = description.mass;
+            let id = graph.add(None, Body::Planet(planet), mesh);
+            graph.node_mut(id).texture = load_texture(description.texture.as_ref())?;
+            graph.node_mut(id).maps = load_maps(description.maps.as_ref(), &mut load_texture)?;
+        }
+
+        for description in &file.rings {
+            let vertex_shader = check_shaders(&description.shader, description.vertex_shader.as_ref())?;
+            let mut ring = Ring::new(
+                description.name.get_ref(),
+                description.inner_radius,
+                description.outer_radius,
+                description.axial_tilt,
+                description.shader.get_ref(),
+            );
+            ring.vertex_shader = vertex_shader;
+            ring.shader_params = description.params.clone();
+            meshes.push(vec![MeshPart { vertices: ring.mesh(description.segments), material: None }]);
+            let id = graph.add(None, Body::Ring(ring), meshes.len() - 1);
+            graph.node_mut(id).texture = load_texture(description.texture.as_ref())?;
+            graph.node_mut(id).maps = load_maps(description.maps.as_ref(), &mut load_texture)?;
+        }
+
+        let description = &file.ship;
+        let vertex_shader = check_shaders(&description.shader, description.vertex_shader.as_ref())?;
+        let [x, y, z] = description.rotation;
+        let mut spaceship = Spaceship::from_model(
+            load_model(&file.asset_path(description.model.get_ref()), description.model.span())?,
+            Vec3::from(description.position),
+            description.scale,
+            Vec3::new(x.to_radians(), y.to_radians(), z.to_radians()),
+            description.shader.get_ref(),
+        );
+        spaceship.name = description.name.get_ref().clone();
+        spaceship.vertex_shader = vertex_shader;
+        spaceship.shader_params = description.params.clone();
+        meshes.push(spaceship.model.get_mesh_parts());
+        let spaceship = graph.add(None, Body::Spaceship(spaceship), meshes.len() - 1);
+
+        // Los padres se asignan al final porque pueden aparecer después que sus hijos
+        for (id, (_, parent)) in file.objects().into_iter().enumerate() {
+            graph.node_mut(id).parent = parent.map(|parent| graph.find(parent.get_ref()).expect("padre validado al leer la escena"));
+        }
+        graph.update_world_matrices();
+
+        let lights = file
+            .lights
+            .iter()
+            .map(|description| {
+                let (color, intensity) = (Color::from_hex(description.color), description.intensity);
+                let light = match description.kind {
+                    LightKind::Point => Light::Point { position: Vec3::from(description.position), color, intensity },
+                    LightKind::Directional => Light::Directional { direction: Vec3::from(description.direction), color, intensity },
+                    LightKind::Ambient => Light::Ambient { color, intensity },
+                };
+                let body = description.body.as_ref().map(|body| graph.find(body.get_ref()).expect("cuerpo validado al leer la escena"));
+                SceneLight { light, body }
+            })
+            .collect();
+
+        let camera = &file.camera;
+        let mut scene = Scene {
+            camera: Camera::new(Vec3::from(camera.eye), Vec3::from(camera.center), Vec3::from(camera.up)),
+            graph,
+            spaceship,
+            background: file.background.color,
+            star_color: file.background.star_color,
+            stars: generate_stars(file.background.stars, width, height, seed),
+            lights,
+            clock: SimClock::default(),
+            interpolation: Interpolation::PerspectiveCorrect,
+            shaders,
+            physics: file.physics,
+            shadows: file.shadows,
+            gravity: None,
+            meshes,
+        };
+        if file.physics.enabled {
+            scene.enable_nbody();
+        }
+        Ok(scene)
+    }
+
+    /// Avanza la simulación un paso fijo del reloj, hacia adelante o hacia atrás.
+    pub fn update(&mut self) {
+        self.graph.save_previous();
+        self.update_bodies(self.clock.delta());
+        self.graph.update_world_matrices();
+        self.clock.tick();
+    }
+
+    /// Avanza el reloj `real_seconds` de tiempo real (multiplicado por su escala) y da
+    /// los pasos fijos que correspondan. Devuelve cuántos pasos se dieron.
+    pub fn advance(&mut self, real_seconds: f64) -> u32 {
+        let steps = self.clock.advance(real_seconds);
+        // Solo el último paso necesita las matrices de mundo y el estado para interpolar
+        for _ in 1..steps {
+            self.update_bodies(self.clock.delta());
+            self.clock.tick();
+        }
+        if steps > 0 {
+            self.update();
+        }
+        steps
+    }
+
+    // Órbitas y giros, y la gravedad si está activa. No recalcula las matrices de mundo
+    fn update_bodies(&mut self, dt: f32) {
+        self.graph.update_bodies(dt);
+        let Some(gravity) = &mut self.gravity else { return };
+
+        gravity.system.step(dt as f64);
+        for (particle, &id) in gravity.system.particles.iter().zip(&gravity.nodes) {
+            let position = particle.position.cast::<f32>();
+            match &mut self.graph.node_mut(id).body {
+                Body::Planet(planet) => planet.free_position = Some(position),
+                Body::Spaceship(spaceship) => spaceship.position = position,
+                Body::Ring(_) => {}
+            }
+        }
+    }
+
+    /// Pasa al modo de N cuerpos con los ajustes de [`Scene::physics`]: los planetas y
+    /// la nave dejan sus órbitas y se mueven por la gravedad de los planetas con masa.
+    /// Cada uno parte de donde está, con la velocidad de una caída libre por su órbita
+    /// alrededor del padre (ver [`crate::orbit::OrbitalElements::velocity`]); la nave
+    /// parte con la del planeta más cercano. La nave se suelta si estaba acoplada y los
+    /// anillos siguen a su planeta.
+    pub fn enable_nbody(&mut self) {
+        if self.gravity.is_some() {
+            return;
+        }
+        self.graph.set_parent(self.spaceship, None);
+
+        let mut system = NBody::new(self.physics.gravity as f64, self.physics.softening as f64, self.physics.integrator);
+        let mut nodes = Vec::new();
+        let mut parents = Vec::new();
+        for (id, node) in self.graph.iter() {
+            if let Body::Planet(planet) = &node.body {
+                system.add(Particle {
+                    mass: planet.mass as f64,
+                    position: node.world_position().cast(),
+                    velocity: self.orbital_velocity(id).cast(),
+                });
+                nodes.push(id);
+                parents.push((id, node.parent));
+            }
+        }
+
+        let ship_position = self.graph.node(self.spaceship).world_position();
+        let ship_velocity = self.nearest_planet(ship_position).map_or(Vec3::zeros(), |id| self.orbital_velocity(id));
+        system.add(Particle { mass: 0.0, position: ship_position.cast(), velocity: ship_velocity.cast() });
+        nodes.push(self.spaceship);
+        // Sin esto el sistema entero se desplaza con el momento de los planetas
+        system.remove_momentum();
+
+        for &(id, _) in &parents {
+            let position = self.graph.node(id).world_position();
+            let node = self.graph.node_mut(id);
+            node.parent = None;
+            if let Body::Planet(planet) = &mut node.body {
+                planet.free_position = Some(position);
+            }
+        }
+        self.graph.update_world_matrices();
+        self.gravity = Some(Gravity { system, nodes, parents });
+    }
+
+    /// Vuelve a las órbitas guionadas. La nave queda donde está.
+    pub fn disable_nbody(&mut self) {
+        let Some(gravity) = self.gravity.take() else { return };
+        for (id, parent) in gravity.parents {
+            let node = self.graph.node_mut(id);
+            node.parent = parent;
+            if let Body::Planet(planet) = &mut node.body {
+                planet.free_position = None;
+            }
+        }
+        self.graph.update_world_matrices();
+    }
+
+    /// Simulación de N cuerpos activa, para leer su estado y sus diagnósticos.
+    pub fn nbody(&self) -> Option<&NBody> {
+        self.gravity.as_ref().map(|gravity| &gravity.system)
+    }
+
+    /// Cambia el integrador, también el de la simulación en curso.
+    pub fn set_integrator(&mut self, integrator: Integrator) {
+        self.physics.integrator = integrator;
+        if let Some(gravity) = &mut self.gravity {
+            gravity.system.set_integrator(integrator);
+        }
+    }
+
+    // Velocidad en el mundo de un planeta que cae libremente por su órbita, sumada a la
+    // de sus ancestros. Los cuerpos sin padre parten quietos
+    fn orbital_velocity(&self, id: NodeId) -> Vec3 {
+        let node = self.graph.node(id);
+        let (Body::Planet(planet), Some(parent)) = (&node.body, node.parent) else {
+            return Vec3::zeros();
+        };
+        let parent_mass = match &self.graph.node(parent).body {
+            Body::Planet(parent) => parent.mass,
+            _ => 0.0,
+        };
+        let mu = self.physics.gravity * (parent_mass + planet.mass);
+        self.orbital_velocity(parent) + planet.orbit.velocity(planet.current_angle, mu)
+    }
+
+    /// Dibuja el frame actual en el framebuffer (que ya debe estar limpio)
+    pub fn render(&self, framebuffer: &mut Framebuffer) -> RenderStats {
+        draw_stars(framebuffer, &self.stars, self.star_color);
+
+        let view_matrix = create_view_matrix(self.camera.eye, self.camera.center, self.camera.up);
+        let projection_matrix = create_perspective_matrix(framebuffer.width as f32, framebuffer.height as f32);
+        let viewport_matrix = create_viewport_matrix(framebuffer.width as f32, framebuffer.height as f32);
+        let mut stats = RenderStats::default();
+
+        let alpha = self.clock.alpha();
+        let lights: Vec<Light> = self
+            .lights
+            .iter()
+            .map(|scene_light| match (scene_light.light, scene_light.body) {
+                (Light::Point { color, intensity, .. }, Some(body)) => {
+                    let position = self.graph.node(body).interpolated_model(alpha).column(3).xyz();
+                    Light::Point { position, color, intensity }
+                }
+                (light, _) => light,
+            })
+            .collect();
+        let shadow = self.shadow_map(alpha).map(Arc::new);
+
+        for (_, node) in self.graph.iter() {
+            let body = &node.body;
+            let params = self.shaders.resolve_params(body.shader(), body.shader_params());
+            let (vertex_shader, fragment_shader) = self.shaders_for(body.vertex_shader(), body.shader());
+
+            // Cada parte del modelo se dibuja con su material
+            for part in &self.meshes[node.mesh] {
+                let material = part.material.as_deref();
+                let uniforms = Uniforms {
+                    model_matrix: node.interpolated_model(alpha),
+                    view_matrix,
+                    projection_matrix,
+                    viewport_matrix,
+                    time: self.clock.render_time(),
+                    noise: SurfaceNoise::from_params(&params),
+                    params: params.clone(),
+                    lights: lights.clone(),
+                    camera_position: self.camera.eye,
+                    shadow: shadow.clone(),
+                    texture: material.map_or(node.texture.clone(), |material| material.texture(node.texture.as_ref())),
+                    maps: material.map_or(node.maps.clone(), |material| material.maps(&node.maps)),
+                    material: part.material.clone(),
+                };
+
+                stats += render(
+                    framebuffer,
+                    &uniforms,
+                    &part.vertices,
+                    vertex_shader,
+                    fragment_shader,
+                    &RasterState { interpolation: self.interpolation, ..body.raster_state() },
+                );
+            }
+        }
+
+        stats
+    }
+
+    // Mapa de sombras de la primera luz puntual que sigue a un cuerpo, visto desde su
+    // posición en este frame. El cuerpo de la luz no hace sombra: la encierra
+    fn shadow_map(&self, alpha: f32) -> Option<ShadowMap> {
+        if !self.shadows.enabled || self.shadows.size == 0 {
+            return None;
+        }
+        let light_body = self.lights.iter().find_map(|scene_light| match (scene_light.light, scene_light.body) {
+            (Light::Point { .. }, Some(body)) => Some(body),
+            _ => None,
+        })?;
+
+        let light_position = self.graph.node(light_body).interpolated_model(alpha).column(3).xyz();
+        let mut shadow = ShadowMap::new(light_position, self.shadows.size);
+        for (id, node) in self.graph.iter() {
+            if id == light_body {
+                continue;
+            }
+            let (vertex_shader, _) = self.shaders_for(node.body.vertex_shader(), node.body.shader());
+            for part in &self.meshes[node.mesh] {
+                shadow.render_caster(node.interpolated_model(alpha), &part.vertices, vertex_shader);
+            }
+        }
+        Some(shadow)
+    }
+
+    pub fn spaceship(&self) -> &Spaceship {
+        match &self.graph.node(self.spaceship).body {
+            Body::Spaceship(spaceship) => spaceship,
+            _ => panic!("El nodo de la nave no es una nave"),
+        }
+    }
+
+    pub fn spaceship_mut(&mut self) -> &mut Spaceship {
+        match &mut self.graph.node_mut(self.spaceship).body {
+            Body::Spaceship(spaceship) => spaceship,
+            _ => panic!("El nodo de la nave no es una nave"),
+        }
+    }
+
+    /// Mueve la nave respecto a su padre (el planeta si está acoplada). En el modo de N
+    /// cuerpos, en cambio, la empuja: `direction` cambia su velocidad.
+    pub fn move_spaceship(&mut self, direction: Vec3) {
+        if let Some(gravity) = &mut self.gravity {
+            let ship = gravity.nodes.len() - 1;
+            gravity.system.add_velocity(ship, (direction * SHIP_THRUST).cast());
+            return;
+        }
+        self.spaceship_mut().update_position(direction);
+        self.graph.update_world_matrices();
+    }
+
+    /// Acopla la nave al planeta más cercano, que la lleva consigo en su órbita, o la
+    /// suelta si ya estaba acoplada. La nave no salta: conserva su posición en el mundo.
+    /// Devuelve el planeta al que quedó acoplada. En el modo de N cuerpos la nave vuela
+    /// libre y no se acopla.
+    pub fn toggle_dock(&mut self) -> Option<String> {
+        if self.graph.node(self.spaceship).parent.is_some() {
+            self.graph.set_parent(self.spaceship, None);
+            return None;
+        }
+        if self.gravity.is_some() {
+            return None;
+        }
+
+        let nearest = self.nearest_planet(self.graph.node(self.spaceship).world_position())?;
+        self.graph.set_parent(self.spaceship, Some(nearest));
+        Some(self.graph.node(nearest).body.name().to_string())
+    }
+
+    fn nearest_planet(&self, position: Vec3) -> Option<NodeId> {
+        self.graph
+            .iter()
+            .filter(|(_, node)| matches!(node.body, Body::Planet(_)))
+            .min_by(|(_, a), (_, b)| {
+                let distance = |node: &Node| (node.world_position() - position).norm();
+                distance(a).total_cmp(&distance(b))
+            })
+            .map(|(id, _)| id)
+    }
+
+    /// Nombre, fragment shader y parámetros propios de cada objeto, en orden de dibujo.
+    pub fn objects(&self) -> impl Iterator<Item = (&str, &str, &ShaderParams)> {
+        self.graph.iter().map(|(_, node)| (node.body.name(), node.body.shader(), node.body.shader_params()))
+    }
+
+    pub fn params_mut(&mut self, object: &str) -> Option<&mut ShaderParams> {
+        let id = self.graph.find(object)?;
+        Some(self.graph.node_mut(id).body.shader_params_mut())
+    }
+
+    /// Reemplaza los parámetros de los objetos nombrados (ver `params::load_params`).
+    /// Devuelve los nombres que no corresponden a ningún objeto de la escena.
+    pub fn apply_params(&mut self, objects: Vec<(String, ShaderParams)>) -> Vec<String> {
+        objects
+            .into_iter()
+            .filter_map(|(object, params)| match self.params_mut(&object) {
+                Some(current) => {
+                    *current = params;
+                    None
+                }
+                None => Some(object),
+            })
+            .collect()
+    }
+
+    // Un nombre sin registrar es un error de la escena, no se reemplaza en silencio
+    fn shaders_for(&self, vertex: &str, fragment: &str) -> (&dyn VertexShader, &dyn FragmentShader) {
+        let vertex_shader = self.shaders.vertex(vertex).unwrap_or_else(|| panic!("Vertex shader no registrado: {}", vertex));
+        let fragment_shader = self.shaders.fragment(fragment).unwrap_or_else(|| panic!("Fragment shader no registrado: {}", fragment));
+        (vertex_shader, fragment_shader)
+    }
+}
+
+/// Objeto y parámetro seleccionados para ajustar shaders desde el teclado.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ParamEditor {
+    object: usize,
+    param: usize,
+}
+
+impl ParamEditor {
+    /// Pasa al objeto siguiente y devuelve una descripción de la selección.
+    pub fn next_object(&mut self, scene: &Scene) -> String {
+        self.object = (self.object + 1) % scene.objects().count();
+        self.param = 0;
+        self.describe(scene)
+    }
+
+    /// Pasa al parámetro siguiente (`direction` = 1) o anterior (-1) del objeto.
+    pub fn next_param(&mut self, scene: &Scene, direction: i32) -> String {
+        let count = self.selected_params(scene).len();
+        if count > 0 {
+            self.param = (self.param as i32 + direction).rem_euclid(count as i32) as usize;
+        }
+        self.describe(scene)
+    }
+
+    /// Sube o baja el parámetro seleccionado un paso dentro de su rango.
+    pub fn adjust(&self, scene: &mut Scene, direction: i32) -> String {
+        let Some(spec) = self.selected_params(scene).get(self.param).copied() else {
+            return self.describe(scene);
+        };
+        let (object, shader, params) = scene.objects().nth(self.object).expect("objeto fuera de rango");
+        let object = object.to_string();
+        let current = scene.shaders.resolve_params(shader, params).get(spec.name).unwrap_or_else(|| spec.default_value());
+
+        let value = spec.step(current, direction);
+        scene.params_mut(&object).expect("objeto fuera de rango").set(spec.name, value);
+        self.describe(scene)
+    }
+
+    fn selected_params(&self, scene: &Scene) -> Vec<ParamSpec> {
+        let (_, shader, _) = scene.objects().nth(self.object).expect("objeto fuera de rango");
+        scene.shaders.fragment_params(shader).unwrap_or(&[]).to_vec()
+    }
+
+    fn describe(&self, scene: &Scene) -> String {
+        let (object, shader, params) = scene.objects().nth(self.object).expect("objeto fuera de rango");
+        match self.selected_params(scene).get(self.param) {
+            Some(spec) => {
+                let value = scene.shaders.resolve_params(shader, params).get(spec.name);
+                format!("{} ({}): {} = {}", object, shader, spec.name, value.unwrap_or_else(|| spec.default_value()))
+            }
+            None => format!("{} ({}): sin parámetros", object, shader),
+        }
+    }
+}
+
+// Carga las imágenes de los mapas de un objeto con `load_texture`, que comparte las ya
+// cargadas
+fn load_maps(
+    description: Option<&MapsDescription>,
+    load_texture: &mut impl FnMut(Option<&TextureDescription>) -> io::Result<Option<Arc<Texture>>>,
+) -> io::Result<SurfaceMaps> {
+    let Some(description) = description else {
+        return Ok(SurfaceMaps::default());
+    };
+    Ok(SurfaceMaps {
+        normal: load_texture(description.normal.as_ref())?,
+        bump: load_texture(description.bump.as_ref())?,
+        bump_scale: description.bump_scale,
+        specular: load_texture(description.specular.as_ref())?,
+        shininess: description.shininess,
+    })
+}
+
+fn generate_stars(count: usize, width: usize, height: usize, seed: u64) -> Vec<(usize, usize)> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    (0..count)
+        .map(|_| {
+            (
+                rng.gen_range(0..width),
+                rng.gen_range(0..height),
+            )
+        })
+        .collect()
+}
+
+fn draw_stars(framebuffer: &mut Framebuffer, stars: &[(usize, usize)], color: u32) {
+    framebuffer.set_current_color(color);
+    for &(x, y) in stars {
+        let index = y * framebuffer.width + x;
+        framebuffer.set_color_at_index(index, color, 1.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nbody_mode_keeps_planets_in_orbit() {
+        let mut scene = Scene::solar_system(80, 60, 0);
+        let earth = scene.graph.find("Tierra").unwrap();
+        let sun = scene.graph.find("Sol").unwrap();
+        let scripted = scene.graph.node(earth).world_position();
+
+        scene.enable_nbody();
+        assert_eq!(scene.graph.node(earth).world_position(), scripted);
+        // Un período de la Tierra (2π / 1.2 s)
+        for _ in 0..315 {
+            scene.update();
+        }
+
+        let distance = (scene.graph.node(earth).world_position() - scene.graph.node(sun).world_position()).norm();
+        assert!((distance - scripted.norm()).abs() < 0.2, "distancia al Sol {}", distance);
+        assert!(scene.nbody().unwrap().diagnostics().energy_drift.abs() < 1e-4);
+
+        // Al salir, la Tierra vuelve a su órbita alrededor del Sol
+        scene.disable_nbody();
+        assert_eq!(scene.graph.node(earth).parent, Some(sun));
+        let Body::Planet(planet) = &scene.graph.node(earth).body else { unreachable!() };
+        assert!(planet.free_position.is_none());
+    }
+}
@@ -0,0 +1,607 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Deserializer};
+use toml::Spanned;
+use crate::nbody::Integrator;
+use crate::maps::DEFAULT_SHININESS;
+use crate::orbit::OrbitalElements;
+use crate::params::{params_from_table, parse_color, ShaderParams};
+use crate::shadow::DEFAULT_SHADOW_SIZE;
+use crate::texture::{Filter, Wrap};
+
+/// Escena por defecto: el sistema solar de `assets/scenes/solar_system.toml`.
+pub const DEFAULT_SCENE: &str = include_str!("../assets/scenes/solar_system.toml");
+
+/// Descripción de una escena leída de un archivo TOML: cámara, fondo, luces, cuerpos,
+/// anillos, la nave y la simulación de gravedad. Ver `assets/scenes/solar_system.toml` para un
+/// ejemplo completo.
+///
+/// Los errores de sintaxis, de tipos y las referencias a objetos que no existen se
+/// informan con el número de línea.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SceneFile {
+    pub camera: CameraDescription,
+    #[serde(default)]
+    pub background: BackgroundDescription,
+    #[serde(default = "default_lights", rename = "light")]
+    pub lights: Vec<LightDescription>, // Sin luces en el archivo: un sol en el origen
+    #[serde(default, rename = "body")]
+    pub bodies: Vec<BodyDescription>,
+    #[serde(default, rename = "ring")]
+    pub rings: Vec<RingDescription>,
+    pub ship: ShipDescription,
+    #[serde(default)]
+    pub physics: PhysicsDescription,
+    #[serde(default)]
+    pub shadows: ShadowDescription,
+    #[serde(skip)]
+    origin: String, // Ruta del archivo, para los mensajes de error
+    #[serde(skip)]
+    line_starts: Vec<usize>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CameraDescription {
+    pub eye: [f32; 3],
+    pub center: [f32; 3],
+    #[serde(default = "default_up")]
+    pub up: [f32; 3],
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BackgroundDescription {
+    #[serde(default, deserialize_with = "color")]
+    pub color: u32,
+    #[serde(default = "default_stars")]
+    pub stars: usize,
+    #[serde(default = "default_star_color", deserialize_with = "color")]
+    pub star_color: u32,
+}
+
+impl Default for BackgroundDescription {
+    fn default() -> Self {
+        BackgroundDescription { color: 0x000000, stars: default_stars(), star_color: default_star_color() }
+    }
+}
+
+/// Luz de la escena; ver [`crate::light::Light`].
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LightDescription {
+    #[serde(deserialize_with = "light_kind")]
+    pub kind: LightKind,
+    #[serde(default)]
+    pub body: Option<Spanned<String>>, // Una luz puntual sobre un cuerpo lo sigue
+    #[serde(default)]
+    pub position: [f32; 3], // Si no sigue a un cuerpo
+    #[serde(default = "default_light_direction")]
+    pub direction: [f32; 3],
+    #[serde(default = "default_star_color", deserialize_with = "color")]
+    pub color: u32,
+    #[serde(default = "default_intensity")]
+    pub intensity: f32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LightKind {
+    Point,
+    Directional,
+    Ambient,
+}
+
+/// Modo de N cuerpos: en lugar de seguir sus órbitas, los cuerpos se atraen con
+/// gravedad mutua a partir de su masa. Ver [`crate::nbody`].
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PhysicsDescription {
+    #[serde(default)]
+    pub enabled: bool, // Si no, se activa con la tecla N
+    #[serde(default = "default_integrator", deserialize_with = "integrator")]
+    pub integrator: Integrator,
+    #[serde(default = "default_gravity")]
+    pub gravity: f32,
+    #[serde(default = "default_softening")]
+    pub softening: f32,
+}
+
+impl Default for PhysicsDescription {
+    fn default() -> Self {
+        PhysicsDescription {
+            enabled: false,
+            integrator: default_integrator(),
+            gravity: default_gravity(),
+            softening: default_softening(),
+        }
+    }
+}
+
+/// Sombras de la primera luz puntual que sigue a un cuerpo (el Sol): las lunas, los
+/// anillos y la nave oscurecen a los planetas. Ver [`crate::shadow::ShadowMap`].
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ShadowDescription {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default = "default_shadow_size")]
+    pub size: usize, // Texels por lado de cada cara del mapa
+}
+
+impl Default for ShadowDescription {
+    fn default() -> Self {
+        ShadowDescription { enabled: true, size: default_shadow_size() }
+    }
+}
+
+/// Planeta, luna o estrella.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BodyDescription {
+    pub name: Spanned<String>,
+    #[serde(default)]
+    pub parent: Option<Spanned<String>>,
+    pub radius: f32,
+    #[serde(default)]
+    pub model: Option<Spanned<String>>, // Esfera si no se indica
+    #[serde(default = "default_star_color", deserialize_with = "color")]
+    pub color: u32,
+    pub shader: Spanned<String>,
+    #[serde(default)]
+    pub vertex_shader: Option<Spanned<String>>,
+    #[serde(default, deserialize_with = "params")]
+    pub params: ShaderParams,
+    #[serde(default)]
+    pub orbit: Option<OrbitDescription>, // Sin órbita queda sobre su padre
+    #[serde(default)]
+    pub orbit_speed: f32,
+    #[serde(default)]
+    pub rotation_speed: f32,
+    #[serde(default)]
+    pub axial_tilt: f32,
+    #[serde(default)]
+    pub rotation_phase: f32,
+    #[serde(default)]
+    pub mass: f32, // Sin masa solo siente la gravedad de los demás
+    #[serde(default)]
+    pub texture: Option<TextureDescription>,
+    #[serde(default)]
+    pub maps: Option<MapsDescription>,
+}
+
+/// Imagen PNG o PPM que el shader `textured` (o uno propio) muestrea sobre el objeto;
+/// ver [`crate::texture::Texture`].
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TextureDescription {
+    pub path: Spanned<String>,
+    #[serde(default = "default_filter", deserialize_with = "filter")]
+    pub filter: Filter,
+    #[serde(default = "default_wrap", deserialize_with = "wrap")]
+    pub wrap: Wrap,
+}
+
+/// Mapas de normales, relieve y brillo de un objeto; ver [`crate::maps::SurfaceMaps`].
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MapsDescription {
+    #[serde(default)]
+    pub normal: Option<TextureDescription>,
+    #[serde(default)]
+    pub bump: Option<TextureDescription>,
+    #[serde(default = "default_scale")]
+    pub bump_scale: f32,
+    #[serde(default)]
+    pub specular: Option<TextureDescription>,
+    #[serde(default = "default_shininess")]
+    pub shininess: f32,
+}
+
+/// Elementos orbitales con los ángulos en grados; ver [`OrbitalElements`].
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OrbitDescription {
+    pub semi_major_axis: f32,
+    #[serde(default, deserialize_with = "eccentricity")]
+    pub eccentricity: f32,
+    #[serde(default)]
+    pub inclination: f32,
+    #[serde(default)]
+    pub ascending_node: f32,
+    #[serde(default)]
+    pub argument_of_periapsis: f32,
+    #[serde(default)]
+    pub mean_anomaly: f32,
+}
+
+impl From<OrbitDescription> for OrbitalElements {
+    fn from(orbit: OrbitDescription) -> Self {
+        OrbitalElements::from_degrees(
+            orbit.semi_major_axis,
+            orbit.eccentricity,
+            orbit.inclination,
+            orbit.ascending_node,
+            orbit.argument_of_periapsis,
+            orbit.mean_anomaly,
+        )
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RingDescription {
+    pub name: Spanned<String>,
+    #[serde(default)]
+    pub parent: Option<Spanned<String>>,
+    pub inner_radius: f32,
+    pub outer_radius: f32,
+    #[serde(default)]
+    pub axial_tilt: f32,
+    #[serde(default = "default_segments")]
+    pub segments: usize,
+    pub shader: Spanned<String>,
+    #[serde(default)]
+    pub vertex_shader: Option<Spanned<String>>,
+    #[serde(default, deserialize_with = "params")]
+    pub params: ShaderParams,
+    #[serde(default)]
+    pub texture: Option<TextureDescription>, // u va del borde interior al exterior
+    #[serde(default)]
+    pub maps: Option<MapsDescription>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ShipDescription {
+    pub name: Spanned<String>,
+    #[serde(default)]
+    pub parent: Option<Spanned<String>>, // Acoplada desde el inicio
+    pub model: Spanned<String>,
+    pub position: [f32; 3],
+    #[serde(default = "default_scale")]
+    pub scale: f32,
+    #[serde(default)]
+    pub rotation: [f32; 3], // En grados
+    pub shader: Spanned<String>,
+    #[serde(default)]
+    pub vertex_shader: Option<Spanned<String>>,
+    #[serde(default, deserialize_with = "params")]
+    pub params: ShaderParams,
+}
+
+impl Default for SceneFile {
+    fn default() -> Self {
+        parse_scene(DEFAULT_SCENE, "assets/scenes/solar_system.toml").expect("La escena por defecto es válida")
+    }
+}
+
+impl SceneFile {
+    /// Nombre y padre de cada objeto, en el orden en que se agregan a la escena:
+    /// cuerpos, anillos y al final la nave.
+    pub fn objects(&self) -> Vec<(&Spanned<String>, Option<&Spanned<String>>)> {
+        self.bodies
+            .iter()
+            .map(|body| (&body.name, body.parent.as_ref()))
+            .chain(self.rings.iter().map(|ring| (&ring.name, ring.parent.as_ref())))
+            .chain(std::iter::once((&self.ship.name, self.ship.parent.as_ref())))
+            .collect()
+    }
+
+    /// Ruta de un modelo o una imagen que nombra la escena: las relativas se toman desde
+    /// la carpeta del archivo de escena, no desde donde se ejecuta el programa.
+    pub fn asset_path(&self, path: &str) -> PathBuf {
+        Path::new(&self.origin).parent().unwrap_or(Path::new("")).join(path)
+    }
+
+    /// Error en la línea donde empieza `span`, con el formato `archivo:línea: mensaje`.
+    pub fn error_at(&self, span: Range<usize>, message: &str) -> io::Error {
+        let line = self.line_starts.partition_point(|&start| start <= span.start);
+        io::Error::new(io::ErrorKind::InvalidData, format!("{}:{}: {}", self.origin, line, message))
+    }
+
+    // Nombres repetidos, padres que no existen y ciclos de padres
+    fn validate(&self) -> io::Result<()> {
+        let objects = self.objects();
+        let mut ids = HashMap::new();
+        for (id, (name, _)) in objects.iter().enumerate() {
+            if ids.insert(name.get_ref().as_str(), id).is_some() {
+                return Err(self.error_at(name.span(), &format!("el nombre {:?} está repetido", name.get_ref())));
+            }
+        }
+
+        let mut parents = Vec::with_capacity(objects.len());
+        for (_, parent) in &objects {
+            parents.push(match parent {
+                Some(parent) => Some(
+                    *ids.get(parent.get_ref().as_str())
+                        .ok_or_else(|| self.error_at(parent.span(), &format!("el padre {:?} no existe", parent.get_ref())))?,
+                ),
+                None => None,
+            });
+        }
+
+        // Si subiendo por los padres se pasa por más nodos que los que hay, hay un ciclo
+        for (id, (name, parent)) in objects.iter().enumerate() {
+            let mut ancestor = parents[id];
+            for _ in 0..objects.len() {
+                let Some(current) = ancestor else { break };
+                if current == id {
+                    let span = parent.map_or(name.span(), |parent| parent.span());
+                    return Err(self.error_at(span, &format!("{:?} es su propio ancestro", name.get_ref())));
+                }
+                ancestor = parents[current];
+            }
+        }
+
+        for light in &self.lights {
+            if let Some(body) = &light.body {
+                if !self.bodies.iter().any(|description| description.name.get_ref() == body.get_ref()) {
+                    return Err(self.error_at(body.span(), &format!("la luz sigue a un cuerpo que no existe: {:?}", body.get_ref())));
+                }
+            }
+        }
+
+        for body in &self.bodies {
+            if body.mass < 0.0 {
+                return Err(self.error_at(body.name.span(), "la masa no puede ser negativa"));
+            }
+        }
+
+        for ring in &self.rings {
+            if !(0.0 <= ring.inner_radius && ring.inner_radius < ring.outer_radius) {
+                return Err(self.error_at(ring.name.span(), "el radio interior del anillo debe estar entre 0 y el exterior"));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Lee y valida una escena. Los errores incluyen la ruta y la línea.
+pub fn load_scene(path: &Path) -> io::Result<SceneFile> {
+    let source = fs::read_to_string(path).map_err(|error| io::Error::new(error.kind(), format!("{}: {}", path.display(), error)))?;
+    parse_scene(&source, &path.display().to_string())
+}
+
+/// Igual que [`load_scene`] a partir del texto; `origin` es el nombre que aparece en
+/// los mensajes de error.
+pub fn parse_scene(source: &str, origin: &str) -> io::Result<SceneFile> {
+    let mut scene: SceneFile = toml::from_str(source)
+        .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", origin, error)))?;
+
+    scene.origin = origin.to_string();
+    scene.line_starts = std::iter::once(0).chain(source.match_indices('\n').map(|(index, _)| index + 1)).collect();
+    scene.validate()?;
+    Ok(scene)
+}
+
+fn color<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u32, D::Error> {
+    let text = String::deserialize(deserializer)?;
+    parse_color(&text)
+        .map(|color| color.to_hex())
+        .ok_or_else(|| serde::de::Error::custom(format!("color inválido {:?}, se espera \"#RRGGBB\"", text)))
+}
+
+fn params<'de, D: Deserializer<'de>>(deserializer: D) -> Result<ShaderParams, D::Error> {
+    params_from_table(&toml::Table::deserialize(deserializer)?).map_err(serde::de::Error::custom)
+}
+
+fn light_kind<'de, D: Deserializer<'de>>(deserializer: D) -> Result<LightKind, D::Error> {
+    let kind = String::deserialize(deserializer)?;
+    match kind.as_str() {
+        "point" => Ok(LightKind::Point),
+        "directional" => Ok(LightKind::Directional),
+        "ambient" => Ok(LightKind::Ambient),
+        _ => Err(serde::de::Error::custom(format!(
+            "tipo de luz desconocido {:?}, se espera \"point\", \"directional\" o \"ambient\"",
+            kind
+        ))),
+    }
+}
+
+fn eccentricity<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f32, D::Error> {
+    let eccentricity = f32::deserialize(deserializer)?;
+    if (0.0..1.0).contains(&eccentricity) {
+        Ok(eccentricity)
+    } else {
+        Err(serde::de::Error::custom(format!("excentricidad {} fuera de [0, 1): solo hay órbitas cerradas", eccentricity)))
+    }
+}
+
+fn integrator<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Integrator, D::Error> {
+    let name = String::deserialize(deserializer)?;
+    Integrator::from_name(&name)
+        .ok_or_else(|| serde::de::Error::custom(format!("integrador desconocido {:?}, se espera \"leapfrog\" o \"verlet\"", name)))
+}
+
+fn filter<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Filter, D::Error> {
+    let name = String::deserialize(deserializer)?;
+    Filter::from_name(&name).ok_or_else(|| {
+        serde::de::Error::custom(format!("filtro desconocido {:?}, se espera \"nearest\", \"bilinear\" o \"trilinear\"", name))
+    })
+}
+
+fn wrap<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Wrap, D::Error> {
+    let name = String::deserialize(deserializer)?;
+    Wrap::from_name(&name)
+        .ok_or_else(|| serde::de::Error::custom(format!("modo de borde desconocido {:?}, se espera \"repeat\" o \"clamp\"", name)))
+}
+
+fn default_up() -> [f32; 3] {
+    [0.0, 1.0, 0.0]
+}
+
+// Un sol en el origen y un poco de luz ambiente para el lado nocturno
+fn default_lights() -> Vec<LightDescription> {
+    let light = |kind, intensity| LightDescription {
+        kind,
+        body: None,
+        position: [0.0; 3],
+        direction: default_light_direction(),
+        color: default_star_color(),
+        intensity,
+    };
+    vec![light(LightKind::Point, 1.0), light(LightKind::Ambient, 0.08)]
+}
+
+fn default_light_direction() -> [f32; 3] {
+    [0.0, 0.0, -1.0]
+}
+
+fn default_intensity() -> f32 {
+    1.0
+}
+
+fn default_stars() -> usize {
+    500
+}
+
+fn default_star_color() -> u32 {
+    0xFFFFFF
+}
+
+fn default_segments() -> usize {
+    128
+}
+
+fn default_scale() -> f32 {
+    1.0
+}
+
+fn default_integrator() -> Integrator {
+    Integrator::Leapfrog
+}
+
+fn default_gravity() -> f32 {
+    1.0
+}
+
+fn default_softening() -> f32 {
+    0.05
+}
+
+fn default_filter() -> Filter {
+    Filter::Trilinear
+}
+
+fn default_wrap() -> Wrap {
+    Wrap::Repeat
+}
+
+fn default_shininess() -> f32 {
+    DEFAULT_SHININESS
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_shadow_size() -> usize {
+    DEFAULT_SHADOW_SIZE
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MINIMAL: &str = r##"
+[camera]
+eye = [0.0, 0.0, 10.0]
+center = [0.0, 0.0, 0.0]
+
+[[body]]
+name = "Sol"
+radius = 2.0
+shader = "sun"
+
+[ship]
+name = "Nave"
+model = "assets/models/ship.obj"
+position = [3.0, 0.0, 0.0]
+shader = "vertex_color"
+"##;
+
+    fn error(source: &str) -> String {
+        parse_scene(source, "prueba.toml").unwrap_err().to_string()
+    }
+
+    #[test]
+    fn default_scene_parses() {
+        let scene = SceneFile::default();
+        assert_eq!(scene.bodies.len(), 9);
+        assert_eq!(scene.background.stars, 500);
+        assert_eq!(scene.rings[0].parent.as_ref().unwrap().get_ref(), "Saturno");
+    }
+
+    #[test]
+    fn minimal_scene_uses_defaults() {
+        let scene = parse_scene(MINIMAL, "prueba.toml").unwrap();
+        assert_eq!(scene.camera.up, [0.0, 1.0, 0.0]);
+        assert_eq!(scene.bodies[0].color, 0xFFFFFF);
+        assert!(scene.bodies[0].orbit.is_none());
+        assert_eq!(scene.ship.scale, 1.0);
+        assert!(!scene.physics.enabled);
+        assert_eq!(scene.lights.len(), 2);
+        assert_eq!(scene.physics.integrator, Integrator::Leapfrog);
+        assert!(scene.shadows.enabled);
+        assert_eq!(scene.shadows.size, 256);
+    }
+
+    #[test]
+    fn asset_paths_are_relative_to_the_scene_file() {
+        let scene = parse_scene(MINIMAL, "escenas/prueba.toml").unwrap();
+        assert_eq!(scene.asset_path("../modelos/nave.obj"), Path::new("escenas/../modelos/nave.obj"));
+        assert_eq!(parse_scene(MINIMAL, "prueba.toml").unwrap().asset_path("sol.png"), Path::new("sol.png"));
+        assert_eq!(scene.asset_path("/tmp/sol.png"), Path::new("/tmp/sol.png"));
+    }
+
+    #[test]
+    fn errors_point_to_the_line() {
+        // Tipo incorrecto: lo informa el lector de TOML
+        let message = error(&MINIMAL.replace("radius = 2.0", "radius = \"grande\""));
+        assert!(message.contains("prueba.toml") && message.contains("line 8"), "{}", message);
+
+        // Color inválido, desde la conversión propia
+        let message = error(&MINIMAL.replace("radius = 2.0", "radius = 2.0\ncolor = \"amarillo\""));
+        assert!(message.contains("line 9") && message.contains("color inválido"), "{}", message);
+
+        // Padre que no existe, desde la validación
+        let message = error(&MINIMAL.replace("shader = \"sun\"", "shader = \"sun\"\nparent = \"Vulcano\""));
+        assert!(message.starts_with("prueba.toml:10: el padre \"Vulcano\""), "{}", message);
+
+        let message = error(&MINIMAL.replace("name = \"Nave\"", "name = \"Sol\""));
+        assert!(message.starts_with("prueba.toml:12: el nombre \"Sol\" está repetido"), "{}", message);
+
+        let message = error(&MINIMAL.replace("shader = \"sun\"", "shader = \"sun\"\nparent = \"Nave\"").replace("shader = \"vertex_color\"", "shader = \"vertex_color\"\nparent = \"Sol\""));
+        assert!(message.contains("es su propio ancestro"), "{}", message);
+
+        let message = error(&format!("{}\n[[light]]\nkind = \"spot\"\n", MINIMAL));
+        assert!(message.contains("line 18") && message.contains("tipo de luz desconocido"), "{}", message);
+
+        let message = error(&format!("{}\n[physics]\nintegrator = \"euler\"\n", MINIMAL));
+        assert!(message.contains("line 18") && message.contains("integrador desconocido"), "{}", message);
+
+        let message = error(&MINIMAL.replace("radius = 2.0", "radius = 2.0\ntexture = { path = \"sol.png\", filter = \"anisotropic\" }"));
+        assert!(message.contains("line 9") && message.contains("filtro desconocido"), "{}", message);
+
+        let message = error(&MINIMAL.replace("radius = 2.0", "radius = 2.0\nmaps = { height = { path = \"sol.png\" } }"));
+        assert!(message.contains("line 9") && message.contains("unknown field `height`"), "{}", message);
+    }
+
+    #[test]
+    fn surface_maps_use_defaults() {
+        let source = MINIMAL.replace("radius = 2.0", "radius = 2.0\nmaps = { bump = { path = \"relieve.png\", filter = \"bilinear\" }, specular = { path = \"brillo.png\" } }");
+        let scene = parse_scene(&source, "prueba.toml").unwrap();
+        let maps = scene.bodies[0].maps.as_ref().unwrap();
+
+        assert!(maps.normal.is_none());
+        assert_eq!(maps.bump.as_ref().unwrap().filter, Filter::Bilinear);
+        assert_eq!(maps.bump_scale, 1.0);
+        assert_eq!(maps.specular.as_ref().unwrap().path.get_ref(), "brillo.png");
+        assert_eq!(maps.shininess, DEFAULT_SHININESS);
+    }
+}
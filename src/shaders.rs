@@ -1,9 +1,153 @@
-use nalgebra_glm::{Vec3, Vec4, Mat3, mat4_to_mat3};
+use nalgebra_glm::{Vec2, Vec3, Vec4, Mat3, mat4_to_mat3};
 use crate::vertex::Vertex;
-use crate::Uniforms;
+use crate::render::Uniforms;
 use crate::fragment::Fragment;
 use crate::color::Color;
+use crate::light::Surface;
+use crate::maps::relief_normal;
+use crate::params::{ParamSpec, ShaderParams};
+use std::f32::consts::{FRAC_PI_2, TAU};
 
+/// Nombre del vertex shader que usan los objetos si no piden otro.
+pub const DEFAULT_VERTEX_SHADER: &str = "standard";
+
+/// Etapa de vértices: lleva un vértice del modelo a espacio de recorte.
+pub trait VertexShader: Send + Sync {
+    fn shade(&self, vertex: &Vertex, uniforms: &Uniforms) -> Vertex;
+}
+
+/// Etapa de fragmentos: decide el color de cada píxel cubierto. `None` descarta el
+/// fragmento: no escribe color ni profundidad y se ve lo que hay detrás.
+pub trait FragmentShader: Send + Sync {
+    fn shade(&self, fragment: &Fragment, uniforms: &Uniforms) -> Option<Color>;
+}
+
+// Cualquier función con la firma adecuada sirve como shader; los fragment shaders que
+// descartan fragmentos devuelven `Option<Color>`
+impl<F> VertexShader for F
+where
+    F: Fn(&Vertex, &Uniforms) -> Vertex + Send + Sync,
+{
+    fn shade(&self, vertex: &Vertex, uniforms: &Uniforms) -> Vertex {
+        self(vertex, uniforms)
+    }
+}
+
+impl<F, C> FragmentShader for F
+where
+    F: Fn(&Fragment, &Uniforms) -> C + Send + Sync,
+    C: Into<Option<Color>>,
+{
+    fn shade(&self, fragment: &Fragment, uniforms: &Uniforms) -> Option<Color> {
+        self(fragment, uniforms).into()
+    }
+}
+
+/// Shaders disponibles por nombre, en el orden en que se registraron.
+///
+/// Los objetos de la escena guardan el nombre de su shader; desde fuera del crate se
+/// pueden agregar shaders propios con [`ShaderRegistry::register_fragment`] y
+/// [`ShaderRegistry::register_vertex`]. Un fragment shader puede declarar parámetros
+/// (ver [`ShaderRegistry::register_fragment_with_params`]) que cada objeto ajusta y que
+/// el shader lee de `uniforms.params`.
+#[derive(Default)]
+pub struct ShaderRegistry {
+    vertex: Vec<(String, Box<dyn VertexShader>)>,
+    fragment: Vec<(String, FragmentEntry)>,
+}
+
+struct FragmentEntry {
+    shader: Box<dyn FragmentShader>,
+    params: Vec<ParamSpec>,
+}
+
+impl ShaderRegistry {
+    /// Registro vacío, sin ningún shader.
+    pub fn new() -> Self {
+        ShaderRegistry::default()
+    }
+
+    /// Registro con los shaders incluidos en el crate.
+    pub fn with_builtin() -> Self {
+        let mut registry = ShaderRegistry::new();
+        registry.register_vertex(DEFAULT_VERTEX_SHADER, vertex_shader);
+
+        registry.register_fragment_with_params("sun", SUN_PARAMS, sun_shader);             // Sol dinámico con manchas solares
+        registry.register_fragment_with_params("ripple", RIPPLE_PARAMS, ripple_shader);    // Ondas
+        registry.register_fragment_with_params("earth_clouds", EARTH_PARAMS, earth_clouds); // Tierra con nubes
+        registry.register_fragment_with_params("moon", MOON_PARAMS, moon_shader_bright_craters); // Luna con cráteres
+        registry.register_fragment_with_params("cellular", CELLULAR_PARAMS, dynamic_cellular_shader); // Patrón celular dinámico
+        registry.register_fragment_with_params("noise", NOISE_PARAMS, noise_shader);       // Círculos en movimiento
+        registry.register_fragment_with_params("rings", RINGS_PARAMS, rings_shader);       // Anillos con bandas y división
+        registry.register_fragment("vertex_color", vertex_color_shader);                   // Color de los vértices
+        registry.register_fragment("textured", textured_shader);                           // Textura del objeto
+        registry.register_fragment("material", material_shader);                           // Material del .mtl
+        registry
+    }
+
+    /// Agrega un vertex shader; si el nombre ya existía, lo reemplaza en su lugar.
+    pub fn register_vertex<S: VertexShader + 'static>(&mut self, name: &str, shader: S) {
+        match self.vertex.iter_mut().find(|(existing, _)| existing == name) {
+            Some(existing) => existing.1 = Box::new(shader),
+            None => self.vertex.push((name.to_string(), Box::new(shader))),
+        }
+    }
+
+    /// Agrega un fragment shader; si el nombre ya existía, lo reemplaza en su lugar.
+    pub fn register_fragment<S: FragmentShader + 'static>(&mut self, name: &str, shader: S) {
+        self.register_fragment_with_params(name, &[], shader);
+    }
+
+    /// Agrega un fragment shader que declara parámetros ajustables por objeto.
+    pub fn register_fragment_with_params<S: FragmentShader + 'static>(&mut self, name: &str, params: &[ParamSpec], shader: S) {
+        let entry = FragmentEntry { shader: Box::new(shader), params: params.to_vec() };
+        match self.fragment.iter_mut().find(|(existing, _)| existing == name) {
+            Some(existing) => existing.1 = entry,
+            None => self.fragment.push((name.to_string(), entry)),
+        }
+    }
+
+    pub fn vertex(&self, name: &str) -> Option<&dyn VertexShader> {
+        self.vertex.iter().find(|(existing, _)| existing == name).map(|(_, shader)| shader.as_ref())
+    }
+
+    pub fn fragment(&self, name: &str) -> Option<&dyn FragmentShader> {
+        self.fragment_entry(name).map(|entry| entry.shader.as_ref())
+    }
+
+    /// Parámetros que declara un fragment shader; vacío si no declara ninguno.
+    pub fn fragment_params(&self, name: &str) -> Option<&[ParamSpec]> {
+        self.fragment_entry(name).map(|entry| entry.params.as_slice())
+    }
+
+    /// Juego completo de parámetros para dibujar con `name`: los valores por defecto
+    /// reemplazados por los que fija el objeto.
+    pub fn resolve_params(&self, name: &str, overrides: &ShaderParams) -> ShaderParams {
+        ShaderParams::resolve(self.fragment_params(name).unwrap_or(&[]), overrides)
+    }
+
+    fn fragment_entry(&self, name: &str) -> Option<&FragmentEntry> {
+        self.fragment.iter().find(|(existing, _)| existing == name).map(|(_, entry)| entry)
+    }
+
+    /// Nombres de los fragment shaders, en orden de registro.
+    pub fn fragment_names(&self) -> impl Iterator<Item = &str> {
+        self.fragment.iter().map(|(name, _)| name.as_str())
+    }
+
+    /// Fragment shader que sigue a `current`, volviendo al primero después del último.
+    /// Si `current` no está registrado devuelve el primero; `None` si no hay ninguno.
+    pub fn next_fragment(&self, current: &str) -> Option<&str> {
+        let next = self
+            .fragment
+            .iter()
+            .position(|(name, _)| name == current)
+            .map_or(0, |index| (index + 1) % self.fragment.len());
+        self.fragment.get(next).map(|(name, _)| name.as_str())
+    }
+}
+
+/// Transforma un vértice a espacio de recorte.
 pub fn vertex_shader(vertex: &Vertex, uniforms: &Uniforms) -> Vertex {
     let position = Vec4::new(
         vertex.position.x,
@@ -12,226 +156,477 @@ pub fn vertex_shader(vertex: &Vertex, uniforms: &Uniforms) -> Vertex {
         1.0
     );
 
-    let transformed = uniforms.projection_matrix * uniforms.view_matrix * uniforms.model_matrix * position;
-
-    let w = transformed.w;
-    let transformed_position = Vec4::new(
-        transformed.x / w,
-        transformed.y / w,
-        transformed.z / w,
-        1.0
-    );
-
-    let screen_position = uniforms.viewport_matrix * transformed_position;
+    let clip_position = uniforms.projection_matrix * uniforms.view_matrix * uniforms.model_matrix * position;
 
     let model_mat3 = mat4_to_mat3(&uniforms.model_matrix);
     let normal_matrix = model_mat3.transpose().try_inverse().unwrap_or(Mat3::identity());
 
     let transformed_normal = normal_matrix * vertex.normal;
+    // La tangente está sobre la superficie: se transforma como las posiciones
+    let transformed_tangent = model_mat3 * vertex.tangent.xyz();
+    let world_position = uniforms.model_matrix * position;
 
+    // La división por w y el viewport se aplican después del recorte (ver clipping.rs)
     Vertex {
         position: vertex.position,
         normal: vertex.normal,
         tex_coords: vertex.tex_coords,
+        tangent: vertex.tangent,
         color: vertex.color,
-        transformed_position: Vec3::new(screen_position.x, screen_position.y, screen_position.z),
-        transformed_normal: transformed_normal
+        clip_position,
+        inv_w: 1.0 / clip_position.w,
+        transformed_position: Vec3::new(clip_position.x, clip_position.y, clip_position.z),
+        transformed_normal,
+        transformed_tangent,
+        world_position: world_position.xyz(),
     }
 }
 
-pub fn fragment_shader(fragment: &Fragment, uniforms: &Uniforms, current_shader: u32) -> Color {
-    match current_shader {
-        0 => sun_shader(fragment, uniforms),             // Sol dinámico con manchas solares
-        1 => ripple_shader(fragment, uniforms),          // Shader de ondas
-        2 => earth_clouds(fragment, uniforms),           // Shader Tierra con nubes
-        3 => moon_shader_bright_craters(fragment, uniforms),  // Luna
-        4 => dynamic_cellular_shader(fragment, uniforms), // Patrón celular dinámico
-        5 => noise_shader(fragment, uniforms),           // Ruido para superficies complejas
-        6 => ripple_shader(fragment, uniforms),          // Shader adicional de ondas
-        _ => default_shader(fragment, uniforms),         // Fallback shader
-    }
-}
+// Los shaders de superficie muestrean el ruido en la dirección del punto en espacio del
+// objeto (la esfera del modelo no es perfecta), escalada por `scale`. Las velocidades
+// están en unidades por segundo simulado. El color que calculan es el albedo: al final
+// lo iluminan las luces de los uniforms, salvo el Sol, que emite su propia luz.
+
+const ROCK: Surface = Surface::new(0.05, 8.0);
+const OCEAN: Surface = Surface::new(0.5, 64.0);
+const METAL: Surface = Surface::new(0.6, 32.0);
+
+const SUN_PARAMS: &[ParamSpec] = &[
+    ParamSpec::float("scale", 3.0, 0.5, 20.0),
+    ParamSpec::float("speed", 0.6, 0.0, 6.0),
+    ParamSpec::float("spot_threshold", 0.45, -1.0, 1.0),
+    ParamSpec::int("octaves", 4, 1, 8),
+    ParamSpec::float("lacunarity", 2.0, 1.0, 4.0),
+    ParamSpec::float("gain", 0.5, 0.0, 1.0),
+    ParamSpec::float("warp_amplitude", 0.5, 0.0, 2.0),
+    ParamSpec::color("bright_color", Color::new(255, 255, 102)),  // Amarillo brillante
+    ParamSpec::color("dark_spot_color", Color::new(139, 0, 0)),   // Rojo oscuro
+    ParamSpec::color("base_color", Color::new(255, 69, 0)),       // Superficie roja/anaranjada
+];
 
 fn sun_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
-    let zoom = 50.0;
-    let x = fragment.vertex_position.x;
-    let y = fragment.vertex_position.y;
-    let time = uniforms.time as f32 * 0.01;
-
-    let noise_value = uniforms.noise.get_noise_2d(x * zoom + time, y * zoom + time);
-
-    let bright_color = Color::new(255, 255, 102); // Amarillo brillante
-    let dark_spot_color = Color::new(139, 0, 0);  // Rojo oscuro
-    let base_color = Color::new(255, 69, 0);      // Superficie roja/anaranjada
-
-    let spot_threshold = 0.6;
-
-    let noise_color = if noise_value < spot_threshold {
-        bright_color
+    let params = &uniforms.params;
+    let direction = fragment.vertex_position.normalize();
+    let p = direction * params.float("scale");
+    let t = uniforms.time * params.float("speed");
+
+    // Granulación: fBm sobre un dominio deformado que se desplaza con el tiempo
+    let drift = Vec3::new(t, -0.6 * t, 0.4 * t);
+    let granulation = uniforms.noise.fbm(uniforms.noise.warp(p + drift)) * 0.5 + 0.5;
+    let surface = params.color("base_color").lerp(&params.color("bright_color"), granulation);
+
+    // Manchas: ruido de baja frecuencia que cambia más despacio que la granulación
+    let spots = uniforms.noise.fbm(direction * 1.5 - drift * 0.25);
+    // Emite su propia luz: no se ilumina
+    let spot_threshold = params.float("spot_threshold");
+    if spots > spot_threshold {
+        surface.lerp(&params.color("dark_spot_color"), ((spots - spot_threshold) * 8.0).min(1.0))
     } else {
-        dark_spot_color
-    };
-
-    let final_color = base_color.lerp(&noise_color, noise_value.clamp(0.0, 1.0));
-    final_color * fragment.intensity
+        surface
+    }
 }
 
-fn ripple_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
-    let pos = fragment.vertex_position;
-    let wave_speed = 0.3;
-    let wave_frequency = 10.0;
-    let wave_amplitude = 0.05;
-    let time = uniforms.time as f32 * wave_speed;
+const RIPPLE_PARAMS: &[ParamSpec] = &[
+    ParamSpec::float("wave_speed", 18.0, 0.0, 120.0),
+    ParamSpec::float("wave_frequency", 10.0, 0.0, 50.0),
+    ParamSpec::float("wave_amplitude", 0.05, 0.0, 1.0),
+    ParamSpec::color("base_color", Color::new(70, 130, 180)),     // Azul acero
+    ParamSpec::color("ripple_color", Color::new(173, 216, 230)),  // Azul claro
+];
 
-    let distance = (pos.x.powi(2) + pos.y.powi(2)).sqrt();
-    let ripple = (wave_frequency * (distance - time)).sin() * wave_amplitude;
+fn ripple_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
+    let params = &uniforms.params;
+    let direction = fragment.vertex_position.normalize();
+    let time = uniforms.time * params.float("wave_speed");
 
-    let base_color = Color::new(70, 130, 180); // Azul acero
-    let ripple_color = Color::new(173, 216, 230); // Azul claro
+    // Ondas concéntricas alrededor del polo: la distancia es el ángulo desde el eje Y
+    let distance = direction.y.clamp(-1.0, 1.0).acos();
+    let ripple = (params.float("wave_frequency") * (distance - time)).sin() * params.float("wave_amplitude");
 
     let color_factor = ripple.clamp(0.0, 1.0);
-    let final_color = base_color.lerp(&ripple_color, color_factor);
+    let final_color = params.color("base_color").lerp(&params.color("ripple_color"), color_factor);
 
-    final_color * fragment.intensity
+    uniforms.illuminate(fragment, final_color, ROCK)
 }
 
-fn moon_shader_bright_craters(fragment: &Fragment, uniforms: &Uniforms) -> Color {
-    let zoom = 50.0;
-    let x = fragment.vertex_position.x;
-    let y = fragment.vertex_position.y;
-    let t = uniforms.time as f32 * 0.1;
-
-    let pulsate = (t * 0.5).sin() * 0.05;
-    let surface_noise = uniforms.noise.get_noise_2d(x * zoom + t, y * zoom + t);
-
-    let gray_color = Color::new(200, 200, 200);
-    let bright_crater_color = Color::new(220, 220, 220);
-    let dynamic_color = Color::new(250, 250, 250);
+const MOON_PARAMS: &[ParamSpec] = &[
+    ParamSpec::float("scale", 4.0, 0.5, 20.0),
+    ParamSpec::float("speed", 6.0, 0.0, 60.0),
+    ParamSpec::float("pulse", 0.05, 0.0, 0.5),
+    ParamSpec::float("crater_threshold", -0.6, -1.0, 1.0),
+    ParamSpec::float("relief", 0.02, 0.0, 1.0),
+    ParamSpec::int("octaves", 4, 1, 8),
+    ParamSpec::float("lacunarity", 2.0, 1.0, 4.0),
+    ParamSpec::float("gain", 0.5, 0.0, 1.0),
+    ParamSpec::color("gray_color", Color::new(200, 200, 200)),
+    ParamSpec::color("bright_crater_color", Color::new(220, 220, 220)),
+    ParamSpec::color("dynamic_color", Color::new(250, 250, 250)),
+    ParamSpec::color("mare_color", Color::new(120, 120, 130)),
+];
 
-    let crater_threshold = 0.4 + pulsate;
-
-    let base_color = if surface_noise > crater_threshold {
-        gray_color
-    } else if surface_noise > crater_threshold - 0.1 {
-        bright_crater_color
+fn moon_shader_bright_craters(fragment: &Fragment, uniforms: &Uniforms) -> Color {
+    let params = &uniforms.params;
+    let p = fragment.vertex_position.normalize() * params.float("scale");
+    let t = uniforms.time * params.float("speed");
+
+    let pulsate = (t * 0.5).sin() * params.float("pulse");
+
+    // Mares oscuros con fBm y cráteres en el centro de las celdas de Voronoi
+    let mare = uniforms.noise.fbm(p * 0.5);
+    let crater = uniforms.noise.cellular(p * 2.0 + Vec3::new(0.0, 0.0, 0.1 * mare));
+    let crater_threshold = params.float("crater_threshold") + pulsate;
+
+    // Cada cráter es un hoyo hasta su borde claro: la luz entra por un lado y el otro
+    // queda en sombra. Los mares cambian despacio, así que se usa el del fragmento. El
+    // relieve se suma al de los mapas de la escena, si la Luna tiene
+    let rim = crater_threshold + 0.1;
+    let base_normal = uniforms.maps.perturb_normal(fragment);
+    let normal = relief_normal(fragment, base_normal, &uniforms.model_matrix, params.float("relief"), |position| {
+        let p = position.normalize() * params.float("scale");
+        -(rim - uniforms.noise.cellular(p * 2.0 + Vec3::new(0.0, 0.0, 0.1 * mare))).max(0.0)
+    });
+
+    let highlands = params.color("gray_color").lerp(&params.color("mare_color"), (mare * 2.0).clamp(0.0, 1.0));
+    let base_color = if crater < crater_threshold {
+        params.color("dynamic_color")
+    } else if crater < crater_threshold + 0.1 {
+        params.color("bright_crater_color")
     } else {
-        dynamic_color
+        highlands
     };
 
-    base_color * fragment.intensity
+    uniforms.illuminate_normal(fragment, normal, base_color, Surface::MATTE)
 }
 
+const EARTH_PARAMS: &[ParamSpec] = &[
+    ParamSpec::float("scale", 2.0, 0.5, 20.0),
+    ParamSpec::float("speed", 6.0, 0.0, 60.0),
+    ParamSpec::float("snow_threshold", 0.85, 0.0, 1.0),
+    ParamSpec::float("land_threshold", 0.05, -1.0, 1.0),
+    ParamSpec::float("desert_threshold", 0.0, -1.0, 1.0),
+    ParamSpec::float("mountain_threshold", 0.6, -1.0, 1.0),
+    ParamSpec::float("cloud_scale", 3.0, 0.5, 20.0),
+    ParamSpec::float("cloud_threshold", 0.1, -1.0, 1.0),
+    ParamSpec::int("octaves", 5, 1, 8),
+    ParamSpec::float("lacunarity", 2.0, 1.0, 4.0),
+    ParamSpec::float("gain", 0.5, 0.0, 1.0),
+    ParamSpec::float("warp_amplitude", 0.4, 0.0, 2.0),
+    ParamSpec::color("ocean_color", Color::new(0, 105, 148)),
+    ParamSpec::color("land_color", Color::new(34, 139, 34)),
+    ParamSpec::color("desert_color", Color::new(210, 180, 140)),
+    ParamSpec::color("mountain_color", Color::new(110, 100, 90)),
+    ParamSpec::color("snow_color", Color::new(255, 250, 250)),
+    ParamSpec::color("cloud_color", Color::new(255, 255, 255)),
+    ParamSpec::color("sky_color", Color::new(135, 206, 250)),
+];
+
 fn earth_clouds(fragment: &Fragment, uniforms: &Uniforms) -> Color {
-    let zoom = 80.0;
-    let x = fragment.vertex_position.x;
-    let y = fragment.vertex_position.y;
-    let t = uniforms.time as f32 * 0.1;
-
-    let surface_noise = uniforms.noise.get_noise_2d(x * zoom + t, y * zoom);
-
-    let ocean_color = Color::new(0, 105, 148);
-    let land_color = Color::new(34, 139, 34);
-    let desert_color = Color::new(210, 180, 140);
-    let snow_color = Color::new(255, 250, 250);
-
-    let snow_threshold = 0.7;
-    let land_threshold = 0.4;
-    let desert_threshold = 0.3;
-
-    let base_color = if y.abs() > snow_threshold {
-        snow_color
-    } else if surface_noise > land_threshold {
-        land_color
-    } else if surface_noise > desert_threshold {
-        desert_color
+    let params = &uniforms.params;
+    let direction = fragment.vertex_position.normalize();
+    let p = direction * params.float("scale");
+    let t = uniforms.time * params.float("speed");
+
+    // Continentes con el dominio deformado, para costas irregulares
+    let continents = uniforms.noise.fbm(uniforms.noise.warp(p));
+    let mountains = uniforms.noise.ridged(p * 3.0);
+
+    let (base_color, surface) = if direction.y.abs() > params.float("snow_threshold") {
+        (params.color("snow_color"), ROCK)
+    } else if continents > params.float("land_threshold") {
+        if mountains > params.float("mountain_threshold") {
+            (params.color("mountain_color"), ROCK)
+        } else {
+            (params.color("land_color"), ROCK)
+        }
+    } else if continents > params.float("desert_threshold") {
+        (params.color("desert_color"), ROCK)
     } else {
-        ocean_color
+        (params.color("ocean_color"), OCEAN)
     };
 
-    let cloud_zoom = 100.0;
-    let cloud_noise = uniforms.noise.get_noise_2d(x * cloud_zoom + t * 0.5, y * cloud_zoom + t * 0.5);
-
-    let cloud_color = Color::new(255, 255, 255);
-    let sky_gradient = Color::new(135, 206, 250);
+    // Las nubes se desplazan de oeste a este
+    let cloud_point = direction * params.float("cloud_scale") + Vec3::new(t * 0.05, 0.0, 0.0);
+    let cloud_noise = uniforms.noise.fbm(cloud_point);
 
-    let cloud_intensity = cloud_noise.clamp(0.4, 0.7) - 0.4;
-    let final_color = if cloud_noise > 0.6 {
-        base_color.lerp(&cloud_color, cloud_intensity * 0.5)
+    // Las nubes tapan el brillo del océano
+    let cloud_threshold = params.float("cloud_threshold");
+    let (final_color, surface) = if cloud_noise > cloud_threshold {
+        (base_color.lerp(&params.color("cloud_color"), ((cloud_noise - cloud_threshold) * 3.0).min(0.8)), ROCK)
     } else {
-        base_color.lerp(&sky_gradient, 0.1)
+        (base_color.lerp(&params.color("sky_color"), 0.1), surface)
     };
 
-    final_color * fragment.intensity
+    uniforms.illuminate(fragment, final_color, surface)
 }
 
-fn dynamic_cellular_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
-    let zoom = 30.0;
-    let flow_speed = 0.1;
-    let time = uniforms.time as f32 * flow_speed;
-    
-    let x = fragment.vertex_position.x;
-    let y = fragment.vertex_position.y;
+const CELLULAR_PARAMS: &[ParamSpec] = &[
+    ParamSpec::float("scale", 4.0, 0.5, 20.0),
+    ParamSpec::float("flow_speed", 6.0, 0.0, 60.0),
+    ParamSpec::float("warp_amplitude", 0.3, 0.0, 2.0),
+    ParamSpec::color("energy_color_1", Color::new(255, 69, 0)),
+    ParamSpec::color("energy_color_2", Color::new(255, 140, 0)),
+    ParamSpec::color("energy_color_3", Color::new(255, 215, 0)),
+    ParamSpec::color("energy_color_4", Color::new(255, 255, 153)),
+];
 
-    let cell_noise_value = uniforms.noise.get_noise_3d(x * zoom, y * zoom, time).abs();
+fn dynamic_cellular_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
+    let params = &uniforms.params;
+    let p = fragment.vertex_position.normalize() * params.float("scale");
+    let time = uniforms.time * params.float("flow_speed");
 
-    let energy_color_1 = Color::new(255, 69, 0);
-    let energy_color_2 = Color::new(255, 140, 0);
-    let energy_color_3 = Color::new(255, 215, 0);
-    let energy_color_4 = Color::new(255, 255, 153);
+    // Celdas de Voronoi que fluyen: el dominio deformado se desplaza con el tiempo
+    let flow = uniforms.noise.warp(p + Vec3::new(0.0, time * 0.1, 0.0));
+    let cell_noise_value = uniforms.noise.cellular(flow) * 0.5 + 0.5;
 
     let final_color = if cell_noise_value < 0.2 {
-        energy_color_1
+        params.color("energy_color_1")
     } else if cell_noise_value < 0.5 {
-        energy_color_2
+        params.color("energy_color_2")
     } else if cell_noise_value < 0.8 {
-        energy_color_3
+        params.color("energy_color_3")
     } else {
-        energy_color_4
+        params.color("energy_color_4")
     };
 
-    final_color * fragment.intensity
+    uniforms.illuminate(fragment, final_color, ROCK)
 }
 
+const NOISE_PARAMS: &[ParamSpec] = &[
+    ParamSpec::float("radius", 0.1, 0.0, 1.0),   // Radio angular de cada círculo
+    ParamSpec::float("speed", 0.1, 0.0, 2.0),    // Radianes por segundo hacia el este
+    ParamSpec::float("spacing", 0.3, 0.05, 2.0), // Ángulo entre círculos vecinos
+    ParamSpec::int("grid", 3, 0, 10), // Filas de círculos a cada lado del ecuador
+    ParamSpec::color("circle_color", Color::new(0, 0, 0)),
+    ParamSpec::color("background_color", Color::new(255, 255, 255)),
+];
+
+// Círculos repartidos sobre la esfera en filas de latitud, con la misma separación a lo
+// largo de cada fila, que giran alrededor del eje del planeta
 fn noise_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
-    let pos = fragment.vertex_position;
-    let radius = 0.1;
+    let params = &uniforms.params;
+    let direction = fragment.vertex_position.normalize();
+    let radius = params.float("radius");
+    let spacing = params.float("spacing");
+    let grid = params.int("grid");
+    let drift = uniforms.time * params.float("speed");
+
+    let latitude = direction.y.clamp(-1.0, 1.0).asin();
+    let longitude = direction.x.atan2(direction.z) - drift;
+    let nearest_row = (latitude / spacing).round() as i32;
+
+    // El círculo más cercano puede estar en una fila vecina
+    let in_circle = (nearest_row - 1..=nearest_row + 1).filter(|row| row.abs() <= grid).any(|row| {
+        let row_latitude = row as f32 * spacing;
+        if row_latitude.abs() >= FRAC_PI_2 {
+            return false;
+        }
+        let count = ((TAU * row_latitude.cos() / spacing).floor()).max(1.0);
+        let step = TAU / count;
+        // Cada fila empieza en otra longitud, así el patrón no se repite del otro lado
+        let offset = row as f32 * 0.5 + 0.25;
+        let center_longitude = ((longitude - offset) / step).round() * step + offset;
+        let center = Vec3::new(
+            row_latitude.cos() * center_longitude.sin(),
+            row_latitude.sin(),
+            row_latitude.cos() * center_longitude.cos(),
+        );
+        let rotated = Vec3::new(latitude.cos() * longitude.sin(), direction.y, latitude.cos() * longitude.cos());
+        rotated.dot(&center).clamp(-1.0, 1.0).acos() < radius
+    });
+
+    let albedo = if in_circle { params.color("circle_color") } else { params.color("background_color") };
+    uniforms.illuminate(fragment, albedo, ROCK)
+}
 
-    let speed = 0.2;
-    let time = uniforms.time as f32 * 0.01;
+// Los anillos usan el mesh de ring.rs: el radio en el plano XZ va de su borde interior a 1
+const RINGS_PARAMS: &[ParamSpec] = &[
+    ParamSpec::float("band_frequency", 40.0, 1.0, 200.0),
+    ParamSpec::float("band_contrast", 0.5, 0.0, 1.0),
+    ParamSpec::float("gap_radius", 0.8, 0.0, 1.0),   // División de Cassini
+    ParamSpec::float("gap_width", 0.04, 0.0, 0.5),
+    ParamSpec::int("octaves", 3, 1, 8),
+    ParamSpec::color("inner_color", Color::new(140, 120, 95)),
+    ParamSpec::color("outer_color", Color::new(225, 205, 160)),
+];
+
+fn rings_shader(fragment: &Fragment, uniforms: &Uniforms) -> Option<Color> {
+    let params = &uniforms.params;
+    let radius = Vec2::new(fragment.vertex_position.x, fragment.vertex_position.z).norm();
+
+    // La división se descarta y queda transparente
+    if (radius - params.float("gap_radius")).abs() < params.float("gap_width") * 0.5 {
+        return None;
+    }
 
-    let light_dir = Vec3::new(1.0, 1.0, 1.0).normalize();
-    let normal = fragment.normal.normalize();
-    let intensity = normal.dot(&light_dir).max(0.0);
+    // Bandas: ruido que solo depende del radio, igual en toda la vuelta
+    let bands = uniforms.noise.fbm(Vec3::new(radius * params.float("band_frequency"), 0.0, 0.0)) * 0.5 + 0.5;
+    let contrast = params.float("band_contrast");
+    let color = params.color("inner_color").lerp(&params.color("outer_color"), radius) * (1.0 - contrast + contrast * bands);
 
-    let mut circle_mask = 0.0;
-    for i in -3..=3 {
-        for j in -3..=3 {
-            let offset_x = (i as f32 * 0.3) + (time * speed);
-            let offset_y = (j as f32 * 0.3) + (time * speed * 0.5);
+    // El mesh no tiene cara trasera: del lado que mira la cámara, con la normal hacia
+    // ella. Desde el lado que no da al Sol se ven apagados, como los de verdad
+    let facing_camera = fragment.normal.dot(&(uniforms.camera_position - fragment.world_position)) >= 0.0;
+    let normal = if facing_camera { fragment.normal } else { -fragment.normal };
+    Some(uniforms.illuminate(&Fragment { normal, ..*fragment }, color, Surface::MATTE))
+}
 
-            let dist_to_circle = ((pos.x - offset_x).powi(2) + (pos.y - offset_y).powi(2)).sqrt();
+fn vertex_color_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
+    uniforms.illuminate(fragment, fragment.color, METAL)
+}
 
-            if dist_to_circle < radius {
-                circle_mask = 1.0;
-                break;
-            }
-        }
-        if circle_mask == 1.0 {
-            break;
-        }
-    }
+// La imagen del objeto (ver `Uniforms::texture`), iluminada; sin textura, gris
+fn textured_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
+    let albedo = uniforms.sample_texture(fragment).unwrap_or(fragment.color);
+    uniforms.illuminate(fragment, albedo, Surface::MATTE)
+}
 
-    if circle_mask > 0.5 {
-        Color::new(0, 0, 0) * intensity
-    } else {
-        Color::new(255, 255, 255) * (0.5 + intensity * 0.5)
+// Color y brillo del material .mtl de la parte que se dibuja (ver `Uniforms::material`),
+// por la imagen del material si tiene una. Sin material, como `textured`. Las partes
+// traslúcidas descartan algunos píxeles según un tramado de 4x4 y dejan ver lo de atrás
+fn material_shader(fragment: &Fragment, uniforms: &Uniforms) -> Option<Color> {
+    let Some(material) = &uniforms.material else {
+        return Some(textured_shader(fragment, uniforms));
+    };
+    if material.opacity < 1.0 && dither_threshold(fragment.position) >= material.opacity {
+        return None;
     }
+
+    let albedo = match uniforms.sample_texture(fragment) {
+        Some(texel) => Color::from_vec3(texel.to_vec3().component_mul(&material.diffuse.to_vec3())),
+        None => material.diffuse,
+    };
+    Some(uniforms.illuminate(fragment, albedo, material.surface()))
 }
 
-fn default_shader(fragment: &Fragment, _uniforms: &Uniforms) -> Color {
-    fragment.color
+// Umbral en [0, 1) de la matriz de Bayer de 4x4 para el píxel
+fn dither_threshold(position: Vec2) -> f32 {
+    const BAYER: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+    let (x, y) = (position.x as usize % 4, position.y as usize % 4);
+    (BAYER[y][x] as f32 + 0.5) / 16.0
 }
 
-pub fn switch_shader(current_shader: &mut u32, total_shaders: u32) {
-    *current_shader = (*current_shader + 1) % total_shaders;
-}
\ No newline at end of file
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nalgebra_glm::{rotate_vec3, Mat4};
+    use crate::light::Light;
+    use std::sync::Arc;
+    use crate::maps::SurfaceMaps;
+    use crate::material::Material;
+    use crate::noise::SurfaceNoise;
+
+    fn solid(color: Color) -> impl Fn(&Fragment, &Uniforms) -> Color + Send + Sync {
+        move |_: &Fragment, _: &Uniforms| color
+    }
+
+    #[test]
+    fn next_fragment_cycles_in_registration_order() {
+        let mut registry = ShaderRegistry::new();
+        assert_eq!(registry.next_fragment("a"), None);
+
+        registry.register_fragment("a", solid(Color::new(255, 0, 0)));
+        registry.register_fragment("b", solid(Color::new(0, 255, 0)));
+        registry.register_fragment("c", solid(Color::new(0, 0, 255)));
+
+        assert_eq!(registry.next_fragment("a"), Some("b"));
+        assert_eq!(registry.next_fragment("c"), Some("a"));
+        assert_eq!(registry.next_fragment("desconocido"), Some("a"));
+    }
+
+    #[test]
+    fn registering_an_existing_name_replaces_it_in_place() {
+        let mut registry = ShaderRegistry::with_builtin();
+        let names: Vec<String> = registry.fragment_names().map(str::to_string).collect();
+
+        registry.register_fragment("sun", solid(Color::new(1, 2, 3)));
+
+        assert!(registry.fragment_names().eq(names.iter().map(String::as_str)));
+        assert!(registry.fragment("missing").is_none());
+    }
+
+    #[test]
+    fn surface_shaders_do_not_mirror_front_to_back() {
+        let registry = ShaderRegistry::with_builtin();
+
+        for name in ["sun", "earth_clouds", "moon", "cellular", "noise"] {
+            let params = registry.resolve_params(name, &ShaderParams::new());
+            let uniforms = Uniforms {
+                model_matrix: Mat4::identity(),
+                view_matrix: Mat4::identity(),
+                projection_matrix: Mat4::identity(),
+                viewport_matrix: Mat4::identity(),
+                time: 0.0,
+                noise: SurfaceNoise::from_params(&params),
+                params,
+                lights: vec![Light::Ambient { color: Color::new(255, 255, 255), intensity: 1.0 }],
+                camera_position: Vec3::new(0.0, 0.0, 5.0),
+                shadow: None,
+                texture: None,
+                maps: SurfaceMaps::default(),
+                material: None,
+            };
+            let shader = registry.fragment(name).unwrap();
+            let shade = |position: Vec3| {
+                let fragment = Fragment::new(0.0, 0.0, Color::black(), 0.0, position, position, position);
+                shader.shade(&fragment, &uniforms)
+            };
+
+            // Con ruido 2D sobre x/y, cada punto y su reflejo en z tenían el mismo color.
+            // Ahora coinciden tan seguido como con otro punto de la misma latitud, es decir,
+            // solo donde la superficie es uniforme (la Luna y las celdas tienen zonas
+            // grandes de un solo color, los círculos de "noise" van en filas de latitud)
+            let points: Vec<Vec3> = (0..64)
+                .map(|i| {
+                    let angle = i as f32 * 0.37;
+                    Vec3::new(angle.cos() * 0.3, angle.sin() * 0.3, 0.4)
+                })
+                .collect();
+            let matches = |partner: &dyn Fn(Vec3) -> Vec3| points.iter().filter(|&&point| shade(point) == shade(partner(point))).count();
+            let mirrored = matches(&|p| Vec3::new(p.x, p.y, -p.z));
+            // Promedio de varios giros alrededor del eje, para no depender de uno solo
+            let unrelated = (1..=5).map(|turn| matches(&|p| rotate_vec3(&p, turn as f32, &Vec3::y()))).sum::<usize>() / 5;
+
+            assert!(mirrored <= unrelated + 8, "{}: {} de 64 puntos iguales a su reflejo, {} a otro de su latitud", name, mirrored, unrelated);
+        }
+    }
+
+    #[test]
+    fn translucent_materials_discard_part_of_the_pixels() {
+        let glass = Material {
+            name: "Vidrio".to_string(),
+            diffuse: Color::new(100, 150, 200),
+            specular: 0.0,
+            shininess: 1.0,
+            opacity: 0.25,
+            diffuse_map: None,
+            bump_map: None,
+            bump_scale: 1.0,
+        };
+        let uniforms = Uniforms {
+            model_matrix: Mat4::identity(),
+            view_matrix: Mat4::identity(),
+            projection_matrix: Mat4::identity(),
+            viewport_matrix: Mat4::identity(),
+            time: 0.0,
+            noise: SurfaceNoise::from_params(&ShaderParams::new()),
+            params: ShaderParams::new(),
+            lights: vec![Light::Ambient { color: Color::new(255, 255, 255), intensity: 1.0 }],
+            camera_position: Vec3::new(0.0, 0.0, 5.0),
+            shadow: None,
+            texture: None,
+            maps: SurfaceMaps::default(),
+            material: Some(Arc::new(glass)),
+        };
+
+        let colors: Vec<Option<Color>> = (0..16)
+            .map(|i| {
+                let fragment = Fragment::new((i % 4) as f32, (i / 4) as f32, Color::black(), 0.0, Vec3::z(), Vec3::z(), Vec3::z());
+                material_shader(&fragment, &uniforms)
+            })
+            .collect();
+
+        // Un cuarto de cada bloque de 4x4 se dibuja, con el color del material
+        assert_eq!(colors.iter().filter(|&&color| color == Some(Color::new(100, 150, 200))).count(), 4);
+        assert_eq!(colors.iter().filter(|color| color.is_none()).count(), 12);
+    }
+}
@@ -0,0 +1,204 @@
+use nalgebra_glm::{look_at, perspective, Mat4, Vec3};
+use std::f32::consts::{FRAC_PI_2, SQRT_2};
+use crate::color::Color;
+use crate::fragment::Fragment;
+use crate::framebuffer::Framebuffer;
+use crate::maps::SurfaceMaps;
+use crate::noise::SurfaceNoise;
+use crate::params::ShaderParams;
+use crate::render::{render, Uniforms};
+use crate::shaders::VertexShader;
+use crate::transform::create_viewport_matrix;
+use crate::triangle::{CullMode, RasterState};
+use crate::vertex::Vertex;
+
+/// Tamaño por defecto de cada cara del mapa, en texels.
+pub const DEFAULT_SHADOW_SIZE: usize = 256;
+
+// Rango de profundidad de las caras. El cuerpo que emite la luz no proyecta sombra, así
+// que el plano cercano puede quedar dentro de él
+const NEAR: f32 = 0.5;
+const FAR: f32 = 200.0;
+
+// El punto se desplaza hacia afuera de la superficie este múltiplo del tamaño de un
+// texel antes de buscarlo en el mapa, para que una superficie no se sombree a sí misma
+const NORMAL_OFFSET: f32 = 1.5;
+const DEPTH_BIAS: f32 = 1e-5;
+
+// Dirección y vector "arriba" de cada cara: +X, -X, +Y, -Y, +Z, -Z
+const FACES: [(Vec3, Vec3); 6] = [
+    (Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, -1.0, 0.0)),
+    (Vec3::new(-1.0, 0.0, 0.0), Vec3::new(0.0, -1.0, 0.0)),
+    (Vec3::new(0.0, 1.0, 0.0), Vec3::new(0.0, 0.0, 1.0)),
+    (Vec3::new(0.0, -1.0, 0.0), Vec3::new(0.0, 0.0, -1.0)),
+    (Vec3::new(0.0, 0.0, 1.0), Vec3::new(0.0, -1.0, 0.0)),
+    (Vec3::new(0.0, 0.0, -1.0), Vec3::new(0.0, -1.0, 0.0)),
+];
+
+/// Mapa de sombras omnidireccional de una luz puntual: la profundidad de lo que ve la luz
+/// en las seis caras de un cubo a su alrededor. Se dibuja con el mismo rasterizador que
+/// la escena, sin color.
+pub struct ShadowMap {
+    pub light_position: Vec3,
+    pub size: usize,
+    faces: Vec<Framebuffer>,
+    view_projections: [Mat4; 6],
+}
+
+impl ShadowMap {
+    /// Mapa vacío: todavía nada hace sombra.
+    pub fn new(light_position: Vec3, size: usize) -> Self {
+        // Cada cara cubre 90° en ambos ejes
+        let projection = perspective(1.0, FRAC_PI_2, NEAR, FAR);
+        let view_projections = FACES.map(|(direction, up)| projection * look_at(&light_position, &(light_position + direction), &up));
+
+        ShadowMap {
+            light_position,
+            size,
+            faces: (0..FACES.len()).map(|_| Framebuffer::new(size, size)).collect(),
+            view_projections,
+        }
+    }
+
+    /// Agrega un objeto que hace sombra, con su matriz de modelo y su vertex shader. Se
+    /// dibujan las dos caras de cada triángulo, solo en las caras del cubo que toca la
+    /// esfera envolvente del mesh (un vertex shader que lo deforme más allá de ella puede
+    /// perder parte de la sombra).
+    pub fn render_caster(&mut self, model_matrix: Mat4, vertices: &[Vertex], vertex_shader: &dyn VertexShader) {
+        let center = model_matrix.column(3).xyz() - self.light_position;
+        let scale = (0..3).map(|axis| model_matrix.column(axis).xyz().norm()).fold(0.0, f32::max);
+        let radius = vertices.iter().map(|vertex| vertex.position.norm()).fold(0.0, f32::max) * scale;
+
+        let params = ShaderParams::new();
+        let viewport_matrix = create_viewport_matrix(self.size as f32, self.size as f32);
+        let raster_state = RasterState { cull_mode: CullMode::None, ..RasterState::default() };
+        // Solo importa la profundidad: el shader nunca descarta y el color se ignora
+        let depth_only = |_: &Fragment, _: &Uniforms| Color::new(255, 255, 255);
+
+        for (index, (face, view_projection)) in self.faces.iter_mut().zip(&self.view_projections).enumerate() {
+            if !sphere_touches_face(index, center, radius) {
+                continue;
+            }
+            let uniforms = Uniforms {
+                model_matrix,
+                view_matrix: *view_projection,
+                projection_matrix: Mat4::identity(),
+                viewport_matrix,
+                time: 0.0,
+                noise: SurfaceNoise::from_params(&params),
+                params: params.clone(),
+                lights: Vec::new(),
+                camera_position: self.light_position,
+                shadow: None,
+                texture: None,
+                maps: SurfaceMaps::default(),
+                material: None,
+            };
+            render(face, &uniforms, vertices, vertex_shader, &depth_only, &raster_state);
+        }
+    }
+
+    /// Fracción de la luz que llega a un punto de una superficie con normal `normal`: 1
+    /// iluminado, 0 en sombra. Promedia 3x3 texels alrededor del punto (PCF), así el
+    /// borde de la sombra no se ve escalonado.
+    pub fn visibility(&self, position: Vec3, normal: Vec3) -> f32 {
+        let distance = (position - self.light_position).norm();
+        // A esa distancia, una cara de 90° mide el doble de la distancia
+        let texel = 2.0 * distance / self.size as f32;
+        let point = position + normal.normalize() * texel * NORMAL_OFFSET;
+
+        let face = face_for(point - self.light_position);
+        let clip = self.view_projections[face] * point.push(1.0);
+        if clip.w <= 0.0 {
+            return 1.0;
+        }
+        let ndc = clip.xyz() / clip.w;
+        let x = ((ndc.x * 0.5 + 0.5) * self.size as f32).floor() as i32;
+        let y = ((0.5 - ndc.y * 0.5) * self.size as f32).floor() as i32;
+
+        let depths = &self.faces[face].zbuffer;
+        let last = self.size as i32 - 1;
+        let mut lit = 0;
+        for dy in -1..=1 {
+            for dx in -1..=1 {
+                let (sx, sy) = ((x + dx).clamp(0, last) as usize, (y + dy).clamp(0, last) as usize);
+                if ndc.z <= depths[sy * self.size + sx] + DEPTH_BIAS {
+                    lit += 1;
+                }
+            }
+        }
+        lit as f32 / 9.0
+    }
+}
+
+// Cara del cubo hacia la que apunta una dirección: la de su componente más grande
+fn face_for(direction: Vec3) -> usize {
+    let absolute = direction.abs();
+    if absolute.x >= absolute.y && absolute.x >= absolute.z {
+        if direction.x >= 0.0 { 0 } else { 1 }
+    } else if absolute.y >= absolute.z {
+        if direction.y >= 0.0 { 2 } else { 3 }
+    } else if direction.z >= 0.0 {
+        4
+    } else {
+        5
+    }
+}
+
+// Si una esfera (centro relativo a la luz) entra en la pirámide de una cara: los cuatro
+// planos laterales pasan por la luz a 45° de la dirección de la cara
+fn sphere_touches_face(face: usize, center: Vec3, radius: f32) -> bool {
+    let (direction, up) = FACES[face];
+    let forward = center.dot(&direction);
+    let side = direction.cross(&up);
+    [up, side].iter().all(|axis| forward - center.dot(axis).abs() >= -radius * SQRT_2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shaders::vertex_shader;
+
+    // Cuadrado de 2x2 en el plano XY, mirando a +Z
+    fn square() -> Vec<Vertex> {
+        let corner = |x: f32, y: f32| Vertex::new(Vec3::new(x, y, 0.0), Vec3::z(), Default::default());
+        vec![
+            corner(-1.0, -1.0), corner(1.0, -1.0), corner(1.0, 1.0),
+            corner(-1.0, -1.0), corner(1.0, 1.0), corner(-1.0, 1.0),
+        ]
+    }
+
+    #[test]
+    fn occluders_cast_shadows_in_every_direction() {
+        let mut shadow = ShadowMap::new(Vec3::zeros(), 64);
+        // Un cuadrado a 2 unidades de la luz en cada eje
+        for (direction, _) in FACES {
+            let model = Mat4::new_translation(&(direction * 2.0)) * crate::transform::create_model_matrix(
+                Vec3::zeros(),
+                1.0,
+                if direction.z != 0.0 { Vec3::zeros() } else { Vec3::new(direction.y * FRAC_PI_2, direction.x * FRAC_PI_2, 0.0) },
+            );
+            shadow.render_caster(model, &square(), &vertex_shader);
+        }
+
+        for (direction, _) in FACES {
+            // Detrás del cuadrado, en sombra; delante de él, iluminado
+            assert_eq!(shadow.visibility(direction * 5.0, -direction), 0.0, "{:?}", direction);
+            assert_eq!(shadow.visibility(direction * 1.0, -direction), 1.0, "{:?}", direction);
+        }
+        // Fuera del cuadrado, al costado, no hay sombra
+        assert_eq!(shadow.visibility(Vec3::new(5.0, 4.0, 4.5), -Vec3::x()), 1.0);
+    }
+
+    #[test]
+    fn lit_surfaces_do_not_shadow_themselves() {
+        let mut shadow = ShadowMap::new(Vec3::zeros(), 64);
+        let model = Mat4::new_translation(&Vec3::new(0.0, 0.0, -3.0));
+        shadow.render_caster(model, &square(), &vertex_shader);
+
+        // Puntos sobre el propio cuadrado, con la normal hacia la luz
+        for x in [-0.8, -0.3, 0.0, 0.5, 0.9] {
+            assert_eq!(shadow.visibility(Vec3::new(x, x * 0.5, -3.0), Vec3::z()), 1.0, "x = {}", x);
+        }
+    }
+}
@@ -0,0 +1,50 @@
+use nalgebra_glm::{Vec3, Mat4};
+use crate::obj::Obj;
+use crate::params::ShaderParams;
+use crate::shaders::DEFAULT_VERTEX_SHADER;
+use crate::transform::create_model_matrix;
+use crate::triangle::{CullMode, FrontFace, RasterState};
+
+/// Nave controlable por el jugador, con su propio modelo y shader.
+pub struct Spaceship {
+    pub name: String,
+    pub position: Vec3,
+    pub scale: f32,
+    pub rotation: Vec3,
+    pub model: Obj, // El modelo .obj cargado
+    pub shader: String, // Fragment shader que usará la nave
+    pub vertex_shader: String,
+    pub shader_params: ShaderParams,
+    pub raster_state: RasterState,
+}
+
+impl Spaceship {
+    pub fn new(model_path: &str, position: Vec3, scale: f32, rotation: Vec3, shader: &str) -> Self {
+        let model = Obj::load(model_path).expect("Failed to load spaceship model");
+        Spaceship::from_model(model, position, scale, rotation, shader)
+    }
+
+    /// Igual que `new`, con un modelo ya cargado.
+    pub fn from_model(model: Obj, position: Vec3, scale: f32, rotation: Vec3, shader: &str) -> Self {
+        Spaceship {
+            name: "Nave".to_string(),
+            position,
+            scale,
+            rotation,
+            model,
+            shader: shader.to_string(),
+            vertex_shader: DEFAULT_VERTEX_SHADER.to_string(),
+            shader_params: ShaderParams::new(),
+            // El modelo de la nave no tiene un orden de vértices consistente, no se descartan caras
+            raster_state: RasterState::new(CullMode::None, FrontFace::CounterClockwise),
+        }
+    }
+
+    pub fn update_position(&mut self, direction: Vec3) {
+        self.position += direction;
+    }
+
+    pub fn get_model_matrix(&self) -> Mat4 {
+        create_model_matrix(self.position, self.scale, self.rotation)
+    }
+}
@@ -0,0 +1,277 @@
+use nalgebra_glm::{Vec2, Vec3};
+use std::io;
+use std::path::Path;
+use crate::color::Color;
+use crate::export::load_pixels;
+
+/// Cómo se combinan los texels alrededor del punto muestreado.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Filter {
+    /// El texel más cercano de la imagen original.
+    Nearest,
+    /// Interpola los cuatro texels más cercanos de la imagen original.
+    Bilinear,
+    /// Bilineal en los dos mipmaps más cercanos al tamaño del píxel, mezclados: sin
+    /// parpadeo cuando la textura se ve de lejos.
+    Trilinear,
+}
+
+impl Filter {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "nearest" => Some(Filter::Nearest),
+            "bilinear" => Some(Filter::Bilinear),
+            "trilinear" => Some(Filter::Trilinear),
+            _ => None,
+        }
+    }
+}
+
+/// Qué pasa con las coordenadas fuera de [0, 1].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Wrap {
+    Repeat, // La imagen se repite, como en un mapa de planeta que da la vuelta
+    Clamp,  // Se estira el borde
+}
+
+impl Wrap {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "repeat" => Some(Wrap::Repeat),
+            "clamp" => Some(Wrap::Clamp),
+            _ => None,
+        }
+    }
+}
+
+// Un nivel de la cadena de mipmaps
+#[derive(Clone, Debug)]
+struct Level {
+    width: usize,
+    height: usize,
+    texels: Vec<Color>,
+}
+
+impl Level {
+    fn texel(&self, x: i64, y: i64, wrap: Wrap) -> Vec3 {
+        let (x, y) = match wrap {
+            Wrap::Repeat => (x.rem_euclid(self.width as i64), y.rem_euclid(self.height as i64)),
+            Wrap::Clamp => (x.clamp(0, self.width as i64 - 1), y.clamp(0, self.height as i64 - 1)),
+        };
+        self.texels[y as usize * self.width + x as usize].to_vec3()
+    }
+
+    fn nearest(&self, uv: Vec2, wrap: Wrap) -> Vec3 {
+        let x = (uv.x * self.width as f32).floor() as i64;
+        let y = (uv.y * self.height as f32).floor() as i64;
+        self.texel(x, y, wrap)
+    }
+
+    fn bilinear(&self, uv: Vec2, wrap: Wrap) -> Vec3 {
+        // Los centros de los texels están en medio texel
+        let x = uv.x * self.width as f32 - 0.5;
+        let y = uv.y * self.height as f32 - 0.5;
+        let (x0, y0) = (x.floor(), y.floor());
+        let (tx, ty) = (x - x0, y - y0);
+        let (x0, y0) = (x0 as i64, y0 as i64);
+
+        let top = self.texel(x0, y0, wrap).lerp(&self.texel(x0 + 1, y0, wrap), tx);
+        let bottom = self.texel(x0, y0 + 1, wrap).lerp(&self.texel(x0 + 1, y0 + 1, wrap), tx);
+        top.lerp(&bottom, ty)
+    }
+
+    // Mitad de tamaño, promediando cada bloque de 2x2 (en los lados impares se repite el
+    // último texel)
+    fn downsample(&self) -> Level {
+        let (width, height) = ((self.width / 2).max(1), (self.height / 2).max(1));
+        let texels = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| {
+                let (x, y) = (2 * x as i64, 2 * y as i64);
+                let sum = self.texel(x, y, Wrap::Clamp)
+                    + self.texel(x + 1, y, Wrap::Clamp)
+                    + self.texel(x, y + 1, Wrap::Clamp)
+                    + self.texel(x + 1, y + 1, Wrap::Clamp);
+                Color::from_vec3(sum / 4.0)
+            })
+            .collect();
+        Level { width, height, texels }
+    }
+}
+
+/// Imagen que los fragment shaders muestrean con las coordenadas de textura del
+/// fragmento. Al crearla se generan todos sus mipmaps, hasta 1x1.
+#[derive(Clone, Debug)]
+pub struct Texture {
+    pub filter: Filter,
+    pub wrap: Wrap,
+    levels: Vec<Level>, // El primero es la imagen original
+}
+
+impl Texture {
+    /// Textura a partir de píxeles 0xRRGGBB, fila por fila desde arriba. Filtro trilineal
+    /// y coordenadas que se repiten.
+    pub fn from_pixels(width: usize, height: usize, pixels: &[u32]) -> Self {
+        assert!(width > 0 && height > 0, "Textura vacía");
+        assert_eq!(pixels.len(), width * height, "La textura no tiene {}x{} píxeles", width, height);
+
+        let mut levels = vec![Level { width, height, texels: pixels.iter().map(|&pixel| Color::from_hex(pixel)).collect() }];
+        while let Some(last) = levels.last().filter(|level| level.width > 1 || level.height > 1) {
+            levels.push(last.downsample());
+        }
+
+        Texture { filter: Filter::Trilinear, wrap: Wrap::Repeat, levels }
+    }
+
+    /// Lee una imagen PNG o PPM (ver [`load_pixels`]).
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let (width, height, pixels) = load_pixels(path)?;
+        if width == 0 || height == 0 {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "imagen vacía"));
+        }
+        Ok(Texture::from_pixels(width, height, &pixels))
+    }
+
+    pub fn with_sampling(mut self, filter: Filter, wrap: Wrap) -> Self {
+        self.filter = filter;
+        self.wrap = wrap;
+        self
+    }
+
+    pub fn width(&self) -> usize {
+        self.levels[0].width
+    }
+
+    pub fn height(&self) -> usize {
+        self.levels[0].height
+    }
+
+    /// Cantidad de niveles, contando la imagen original.
+    pub fn mip_levels(&self) -> usize {
+        self.levels.len()
+    }
+
+    /// Color en `uv` (u hacia la derecha, v hacia abajo). `dx` y `dy` son cuánto cambian
+    /// las coordenadas de un píxel de pantalla al siguiente (ver
+    /// [`crate::fragment::Fragment::tex_coords_dx`]); solo el filtro trilineal los usa,
+    /// para elegir el mipmap.
+    pub fn sample(&self, uv: Vec2, dx: Vec2, dy: Vec2) -> Color {
+        Color::from_vec3(self.sample_rgb(uv, dx, dy))
+    }
+
+    /// Como [`Texture::sample`], con los canales en [0, 1].
+    pub fn sample_rgb(&self, uv: Vec2, dx: Vec2, dy: Vec2) -> Vec3 {
+        match self.filter {
+            Filter::Nearest => self.levels[0].nearest(uv, self.wrap),
+            Filter::Bilinear => self.levels[0].bilinear(uv, self.wrap),
+            Filter::Trilinear => {
+                let lod = self.level_of_detail(dx, dy);
+                let level = lod.floor() as usize;
+                let finer = self.levels[level].bilinear(uv, self.wrap);
+                match self.levels.get(level + 1) {
+                    Some(coarser) => finer.lerp(&coarser.bilinear(uv, self.wrap), lod.fract()),
+                    None => finer,
+                }
+            }
+        }
+    }
+
+    // log2 de cuántos texels de la imagen original cubre un píxel, en [0, último nivel]
+    fn level_of_detail(&self, dx: Vec2, dy: Vec2) -> f32 {
+        let size = Vec2::new(self.width() as f32, self.height() as f32);
+        let footprint = dx.component_mul(&size).norm().max(dy.component_mul(&size).norm());
+        if footprint <= 1.0 {
+            return 0.0;
+        }
+        footprint.log2().min((self.levels.len() - 1) as f32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BLACK: u32 = 0x000000;
+    const WHITE: u32 = 0xFFFFFF;
+
+    // Tablero de 8x8 con casillas de un texel
+    fn checkerboard() -> Texture {
+        let pixels: Vec<u32> = (0..64).map(|i| if (i % 8 + i / 8) % 2 == 0 { WHITE } else { BLACK }).collect();
+        Texture::from_pixels(8, 8, &pixels)
+    }
+
+    fn texel_center(x: usize, y: usize) -> Vec2 {
+        Vec2::new((x as f32 + 0.5) / 8.0, (y as f32 + 0.5) / 8.0)
+    }
+
+    #[test]
+    fn mipmaps_halve_down_to_one_texel() {
+        let texture = checkerboard();
+        assert_eq!(texture.mip_levels(), 4);
+        assert_eq!(texture.levels.last().unwrap().texels, vec![Color::new(128, 128, 128)]);
+
+        let odd = Texture::from_pixels(5, 2, &[WHITE; 10]);
+        let sizes: Vec<_> = odd.levels.iter().map(|level| (level.width, level.height)).collect();
+        assert_eq!(sizes, vec![(5, 2), (2, 1), (1, 1)]);
+        assert!(odd.levels.iter().all(|level| level.texels.iter().all(|&texel| texel == Color::from_hex(WHITE))));
+    }
+
+    #[test]
+    fn filters_and_wrap_modes() {
+        let zero = Vec2::zeros();
+        let texture = checkerboard().with_sampling(Filter::Nearest, Wrap::Repeat);
+        assert_eq!(texture.sample(texel_center(0, 0), zero, zero), Color::from_hex(WHITE));
+        assert_eq!(texture.sample(texel_center(1, 0), zero, zero), Color::from_hex(BLACK));
+        // Una vuelta completa cae en el mismo texel
+        assert_eq!(texture.sample(texel_center(1, 0) + Vec2::new(1.0, -2.0), zero, zero), Color::from_hex(BLACK));
+
+        // Entre dos texels, la bilineal da el promedio
+        let texture = texture.with_sampling(Filter::Bilinear, Wrap::Repeat);
+        assert_eq!(texture.sample(Vec2::new(1.0 / 8.0, 0.5 / 8.0), zero, zero), Color::new(128, 128, 128));
+        assert_eq!(texture.sample(texel_center(2, 3), zero, zero), Color::from_hex(BLACK));
+
+        // En el borde, repetir mezcla con el lado opuesto y recortar no
+        let corner = Vec2::zeros();
+        assert_eq!(texture.sample(corner, zero, zero), Color::new(128, 128, 128));
+        let clamped = texture.with_sampling(Filter::Bilinear, Wrap::Clamp);
+        assert_eq!(clamped.sample(corner, zero, zero), Color::from_hex(WHITE));
+    }
+
+    #[test]
+    fn trilinear_uses_the_pixel_footprint() {
+        let texture = checkerboard();
+        let uv = texel_center(0, 0);
+
+        // De cerca, un píxel cubre menos de un texel: la imagen original
+        let close = Vec2::new(0.01, 0.0);
+        assert_eq!(texture.sample(uv, close, close.yx()), Color::from_hex(WHITE));
+        // Desde lejos, el píxel cubre todo el tablero: gris
+        let far = Vec2::new(1.0, 0.0);
+        assert_eq!(texture.sample(uv, far, far.yx()), Color::new(128, 128, 128));
+        // Entre medio, el color pasa de uno a otro sin saltos
+        let mut previous = 255;
+        for step in 1..=32 {
+            let footprint = Vec2::new(step as f32 / 32.0, 0.0);
+            let value = texture.sample(uv, footprint, footprint.yx()).to_hex() >> 16;
+            assert!(value <= previous && previous - value < 48, "paso {}: {} -> {}", step, previous, value);
+            previous = value;
+        }
+    }
+
+    #[test]
+    fn loads_saved_images() {
+        let directory = std::env::temp_dir().join(format!("texture_test_{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let pixels = [0xFF0000, 0x00FF00, 0x0000FF, 0x123456, 0xABCDEF, 0x000000];
+
+        for format in [crate::export::ImageFormat::Png, crate::export::ImageFormat::Ppm] {
+            let path = directory.join(format!("textura.{}", format.color_extension()));
+            crate::export::save_pixels(&pixels, 3, 2, &path, format).unwrap();
+
+            let texture = Texture::load(&path).unwrap();
+            assert_eq!((texture.width(), texture.height()), (3, 2));
+            assert_eq!(texture.levels[0].texels, pixels.map(Color::from_hex));
+        }
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -1,56 +1,337 @@
-use nalgebra_glm::{Vec3, dot};
+use nalgebra_glm::{Vec3, Vec4};
 use crate::fragment::Fragment;
 use crate::vertex::Vertex;
 use crate::color::Color;
+use crate::framebuffer::Tile;
+use crate::rasterizer::RenderStats;
+
+/// Qué caras se descartan antes de rasterizar
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CullMode {
+  None,
+  Back,
+  Front,
+}
+
+/// Orden de los vértices (visto desde la cámara) que define la cara frontal
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FrontFace {
+  CounterClockwise,
+  Clockwise,
+}
+
+/// Cómo se interpolan los atributos de los vértices dentro del triángulo
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Interpolation {
+  PerspectiveCorrect,
+  Affine, // Baricéntricas en pantalla, solo para comparar
+}
+
+/// Estado fijo del rasterizador que cada objeto puede configurar
+#[derive(Clone, Copy, Debug)]
+pub struct RasterState {
+  pub cull_mode: CullMode,
+  pub front_face: FrontFace,
+  pub interpolation: Interpolation,
+}
+
+impl RasterState {
+  pub fn new(cull_mode: CullMode, front_face: FrontFace) -> Self {
+    RasterState {
+      cull_mode,
+      front_face,
+      interpolation: Interpolation::PerspectiveCorrect,
+    }
+  }
+}
+
+impl Default for RasterState {
+  fn default() -> Self {
+    RasterState::new(CullMode::Back, FrontFace::CounterClockwise)
+  }
+}
+
+/// Rectángulo de píxeles en pantalla; min inclusivo, max exclusivo
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Bounds {
+  pub min_x: i32,
+  pub min_y: i32,
+  pub max_x: i32,
+  pub max_y: i32,
+}
+
+impl Bounds {
+  pub fn new(min_x: i32, min_y: i32, max_x: i32, max_y: i32) -> Self {
+    Bounds { min_x, min_y, max_x, max_y }
+  }
+
+  /// Caja envolvente de un triángulo en pantalla, recortada a estos límites
+  pub fn triangle_bounds(&self, v1: &Vertex, v2: &Vertex, v3: &Vertex) -> Bounds {
+    let (min_x, min_y, max_x, max_y) = calculate_bounding_box(
+      &v1.transformed_position,
+      &v2.transformed_position,
+      &v3.transformed_position,
+    );
+
+    Bounds {
+      min_x: min_x.max(self.min_x),
+      min_y: min_y.max(self.min_y),
+      max_x: (max_x + 1).min(self.max_x),
+      max_y: (max_y + 1).min(self.max_y),
+    }
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.min_x >= self.max_x || self.min_y >= self.max_y
+  }
+}
+
+/// Decide si un triángulo en coordenadas de pantalla se descarta según su orientación.
+/// El viewport invierte el eje Y, así que un triángulo antihorario en NDC queda con
+/// área positiva según edge_function.
+pub fn is_culled(v1: &Vertex, v2: &Vertex, v3: &Vertex, state: &RasterState) -> bool {
+  if state.cull_mode == CullMode::None {
+    return false;
+  }
+
+  let area = edge_function(&v1.transformed_position, &v2.transformed_position, &v3.transformed_position);
+  let front_facing = match state.front_face {
+    FrontFace::CounterClockwise => area > 0.0,
+    FrontFace::Clockwise => area < 0.0,
+  };
+
+  match state.cull_mode {
+    CullMode::Back => !front_facing,
+    CullMode::Front => front_facing,
+    CullMode::None => false,
+  }
+}
+
+/// Rasteriza un triángulo ya proyectado a pantalla dentro de un tile.
+///
+/// La cobertura de cada muestra se evalúa en punto fijo (1/256 de píxel) con la regla
+/// top-left, de modo que los píxeles sobre una arista compartida pertenecen a uno solo de
+/// los triángulos. Las muestras cubiertas pasan primero la prueba de profundidad contra el
+/// zbuffer del tile; solo si alguna la supera se construye el `Fragment` y se llama a
+/// `shade` una vez por píxel. `shade` devuelve el color a escribir en esas muestras, o
+/// `None` para descartar el fragmento sin tocar el color ni la profundidad.
+pub fn triangle<F>(v1: &Vertex, v2: &Vertex, v3: &Vertex, state: &RasterState, tile: &mut Tile, stats: &mut RenderStats, mut shade: F)
+where
+  F: FnMut(&Fragment) -> Option<u32>,
+{
+  let (v1, mut v2, mut v3) = (v1, v2, v3);
+  let (p1, mut p2, mut p3) = (
+    FixedPoint::from(&v1.transformed_position),
+    FixedPoint::from(&v2.transformed_position),
+    FixedPoint::from(&v3.transformed_position),
+  );
+
+  // Triángulos degenerados (área cero tras ajustar a la rejilla) no cubren ningún píxel
+  let mut triangle_area = fixed_edge_function(&p1, &p2, &p3);
+  if triangle_area == 0 {
+    return;
+  }
+
+  // Se normaliza el orden para que el área sea positiva; el culling ya se decidió antes
+  if triangle_area < 0 {
+    std::mem::swap(&mut v2, &mut v3);
+    std::mem::swap(&mut p2, &mut p3);
+    triangle_area = -triangle_area;
+  }
 
-pub fn triangle(v1: &Vertex, v2: &Vertex, v3: &Vertex) -> Vec<Fragment> {
-  let mut fragments = Vec::new();
   let (a, b, c) = (v1.transformed_position, v2.transformed_position, v3.transformed_position);
 
-  let (min_x, min_y, max_x, max_y) = calculate_bounding_box(&a, &b, &c);
+  let area = tile.bounds().triangle_bounds(v1, v2, v3);
+  if area.is_empty() {
+    return;
+  }
+
+  // Cada arista es opuesta al vértice cuyo peso calcula
+  let start = FixedPoint::pixel_center(area.min_x, area.min_y);
+  let e1 = EdgeStepper::new(&p2, &p3, &start);
+  let e2 = EdgeStepper::new(&p3, &p1, &start);
+  let e3 = EdgeStepper::new(&p1, &p2, &start);
+  let inv_area = 1.0 / triangle_area as f32;
 
-  let light_dir = Vec3::new(0.0, 0.0, 1.0);
+  let sample_offsets = tile.msaa.sample_offsets();
+  let mut sample_depths = [0.0; 8];
 
-  let triangle_area = edge_function(&a, &b, &c);
+  let (mut row1, mut row2, mut row3) = (e1.origin, e2.origin, e3.origin);
 
-  for y in min_y..=max_y {
-    for x in min_x..=max_x {
-      let point = Vec3::new(x as f32 + 0.5, y as f32 + 0.5, 0.0);
+  for y in area.min_y..area.max_y {
+    let (mut w1, mut w2, mut w3) = (row1, row2, row3);
 
-      let (w1, w2, w3) = barycentric_coordinates(&point, &a, &b, &c, triangle_area);
+    for x in area.min_x..area.max_x {
+      // Cobertura y profundidad por muestra; sin MSAA la única muestra es el centro
+      let mut passed_samples = 0u32;
+      let mut first_covered = None;
 
-      if w1 >= 0.0 && w1 <= 1.0 && 
-         w2 >= 0.0 && w2 <= 1.0 &&
-         w3 >= 0.0 && w3 <= 1.0 {
+      for (sample, &(offset_x, offset_y)) in sample_offsets.iter().enumerate() {
+        let (offset_x, offset_y) = (offset_x as i64 * SAMPLE_UNIT, offset_y as i64 * SAMPLE_UNIT);
+        let s1 = e1.offset(w1, offset_x, offset_y);
+        let s2 = e2.offset(w2, offset_x, offset_y);
+        let s3 = e3.offset(w3, offset_x, offset_y);
 
-        let normal = v1.transformed_normal * w1 + v2.transformed_normal * w2 + v3.transformed_normal * w3;
-        let normal = normal.normalize();
+        if e1.covers(s1) && e2.covers(s2) && e3.covers(s3) {
+          first_covered.get_or_insert((s1, s2, s3));
 
-        let intensity = dot(&normal, &light_dir).max(0.0);
+          // La profundidad (z/w) es lineal en pantalla; el resto de atributos no
+          let depth = (a.z * s1 as f32 + b.z * s2 as f32 + c.z * s3 as f32) * inv_area;
+          if tile.depth_test(x as usize, y as usize, sample, depth) {
+            passed_samples |= 1 << sample;
+            sample_depths[sample] = depth;
+          }
+        }
+      }
+
+      // Los atributos se evalúan en el centro del píxel, o en la primera muestra
+      // cubierta si el centro queda fuera del triángulo
+      if let Some(first_covered) = first_covered {
+        let center_covered = e1.covers(w1) && e2.covers(w2) && e3.covers(w3);
+        let (p1, p2, p3) = if center_covered { (w1, w2, w3) } else { first_covered };
+        stats.covered += 1;
+
+        if passed_samples != 0 {
+          let (b1, b2, b3) = (p1 as f32 * inv_area, p2 as f32 * inv_area, p3 as f32 * inv_area);
+          let depth = a.z * b1 + b.z * b2 + c.z * b3;
+
+          let interpolate = |b1: f32, b2: f32, b3: f32| match state.interpolation {
+            Interpolation::PerspectiveCorrect => perspective_correct(b1, b2, b3, v1, v2, v3),
+            Interpolation::Affine => (b1, b2, b3),
+          };
+          let tex_coords_at = |(b1, b2, b3): (f32, f32, f32)| v1.tex_coords * b1 + v2.tex_coords * b2 + v3.tex_coords * b3;
 
-        let base_color = Color::new(100, 100, 100);
-        let lit_color = base_color * intensity;
+          // Las coordenadas de textura en los píxeles vecinos dan sus derivadas, para
+          // elegir el mipmap
+          let next_x = interpolate(b1 + e1.step_x as f32 * inv_area, b2 + e2.step_x as f32 * inv_area, b3 + e3.step_x as f32 * inv_area);
+          let next_y = interpolate(b1 + e1.step_y as f32 * inv_area, b2 + e2.step_y as f32 * inv_area, b3 + e3.step_y as f32 * inv_area);
+          let (b1, b2, b3) = interpolate(b1, b2, b3);
+          let tex_coords = tex_coords_at((b1, b2, b3));
 
-        let depth = a.z * w1 + b.z * w2 + c.z * w3;
+          let normal = v1.transformed_normal * b1 + v2.transformed_normal * b2 + v3.transformed_normal * b3;
+          let normal = normal.normalize();
 
-        let vertex_position = v1.position * w1 + v2.position * w2 + v3.position * w3;
+          // La tangente no se normaliza: en los vértices sin coordenadas de textura vale
+          // cero y los mapas de normales la ignoran
+          let tangent = v1.transformed_tangent * b1 + v2.transformed_tangent * b2 + v3.transformed_tangent * b3;
+          let handedness = v1.tangent.w * b1 + v2.tangent.w * b2 + v3.tangent.w * b3;
 
-        fragments.push(
-            Fragment::new(
-                x as f32,
-                y as f32,
-                lit_color,
-                depth,
-                normal,
-                intensity,
-                vertex_position,
-            )
-        );
+          let vertex_position = v1.position * b1 + v2.position * b2 + v3.position * b3;
+          let world_position = v1.world_position * b1 + v2.world_position * b2 + v3.world_position * b3;
+          let color = v1.color.to_vec3() * b1 + v2.color.to_vec3() * b2 + v3.color.to_vec3() * b3;
+
+          // La iluminación la calcula el fragment shader con las luces de los uniforms
+          let fragment = Fragment::new(
+            x as f32,
+            y as f32,
+            Color::from_vec3(color),
+            depth,
+            normal,
+            vertex_position,
+            world_position,
+          )
+          .with_tex_coords(tex_coords, tex_coords_at(next_x) - tex_coords, tex_coords_at(next_y) - tex_coords)
+          .with_tangent(Vec4::new(tangent.x, tangent.y, tangent.z, handedness));
+
+          // El fragment shader corre una sola vez por píxel y su color se copia a cada
+          // muestra cubierta que pasó la prueba de profundidad
+          stats.shaded += 1;
+          if let Some(color) = shade(&fragment) {
+            for (sample, &sample_depth) in sample_depths.iter().enumerate().take(sample_offsets.len()) {
+              if passed_samples & (1 << sample) != 0 {
+                tile.set_color(x as usize, y as usize, sample, color, sample_depth);
+              }
+            }
+          }
+        } else {
+          stats.depth_rejected += 1;
+        }
       }
+
+      w1 += e1.step_x;
+      w2 += e2.step_x;
+      w3 += e3.step_x;
+    }
+
+    row1 += e1.step_y;
+    row2 += e2.step_y;
+    row3 += e3.step_y;
+  }
+}
+
+// Bits de subpíxel usados para ajustar los vértices a la rejilla de punto fijo
+const SUBPIXEL_BITS: u32 = 8;
+const SUBPIXEL_SCALE: f32 = (1 << SUBPIXEL_BITS) as f32;
+
+// Las posiciones de muestra del MSAA vienen en dieciseisavos de píxel
+const SAMPLE_UNIT: i64 = 1 << (SUBPIXEL_BITS - 4);
+
+// Posición en pantalla ajustada a la rejilla de subpíxeles
+#[derive(Clone, Copy, Debug)]
+struct FixedPoint {
+  x: i64,
+  y: i64,
+}
+
+impl FixedPoint {
+  fn from(position: &Vec3) -> Self {
+    FixedPoint {
+      x: (position.x * SUBPIXEL_SCALE).round() as i64,
+      y: (position.y * SUBPIXEL_SCALE).round() as i64,
+    }
+  }
+
+  // Centro del píxel (x, y), donde se evalúa la cobertura
+  fn pixel_center(x: i32, y: i32) -> Self {
+    let half = 1 << (SUBPIXEL_BITS - 1);
+    FixedPoint {
+      x: ((x as i64) << SUBPIXEL_BITS) + half,
+      y: ((y as i64) << SUBPIXEL_BITS) + half,
+    }
+  }
+}
+
+// Función de arista evaluada de forma incremental, exacta en aritmética entera
+struct EdgeStepper {
+  origin: i64, // Valor en el primer píxel del área
+  step_x: i64, // Incremento al avanzar un píxel en x
+  step_y: i64, // Incremento al avanzar un píxel en y
+  dx: i64,
+  dy: i64,
+  bias: i64,   // 0 para aristas top-left, 1 para las demás
+}
+
+impl EdgeStepper {
+  fn new(a: &FixedPoint, b: &FixedPoint, start: &FixedPoint) -> Self {
+    let (dx, dy) = (b.x - a.x, b.y - a.y);
+
+    // Con Y hacia abajo y área positiva, una arista izquierda sube en y (dy > 0) y una
+    // superior es horizontal con el interior debajo (dx < 0)
+    let is_top_left = dy > 0 || (dy == 0 && dx < 0);
+
+    EdgeStepper {
+      origin: fixed_edge_function(a, b, start),
+      step_x: dy << SUBPIXEL_BITS,
+      step_y: -dx << SUBPIXEL_BITS,
+      dx,
+      dy,
+      bias: if is_top_left { 0 } else { 1 },
     }
   }
 
-  fragments
+  // Valor de la arista desplazado (offset_x, offset_y) subpíxeles desde `value`
+  fn offset(&self, value: i64, offset_x: i64, offset_y: i64) -> i64 {
+    value + offset_x * self.dy - offset_y * self.dx
+  }
+
+  // Un punto exactamente sobre la arista solo se cubre si la arista es top-left
+  fn covers(&self, value: i64) -> bool {
+    value >= self.bias
+  }
+}
+
+fn fixed_edge_function(a: &FixedPoint, b: &FixedPoint, c: &FixedPoint) -> i64 {
+  (c.x - a.x) * (b.y - a.y) - (c.y - a.y) * (b.x - a.x)
 }
 
 fn calculate_bounding_box(v1: &Vec3, v2: &Vec3, v3: &Vec3) -> (i32, i32, i32, i32) {
@@ -62,14 +343,156 @@ fn calculate_bounding_box(v1: &Vec3, v2: &Vec3, v3: &Vec3) -> (i32, i32, i32, i3
     (min_x, min_y, max_x, max_y)
 }
 
-fn barycentric_coordinates(p: &Vec3, a: &Vec3, b: &Vec3, c: &Vec3, area: f32) -> (f32, f32, f32) {
-    let w1 = edge_function(b, c, p) / area;
-    let w2 = edge_function(c, a, p) / area;
-    let w3 = edge_function(a, b, p) / area;
+// Corrige las baricéntricas de pantalla usando 1/w de cada vértice
+fn perspective_correct(w1: f32, w2: f32, w3: f32, v1: &Vertex, v2: &Vertex, v3: &Vertex) -> (f32, f32, f32) {
+    let p1 = w1 * v1.inv_w;
+    let p2 = w2 * v2.inv_w;
+    let p3 = w3 * v3.inv_w;
+    let sum = p1 + p2 + p3;
 
-    (w1, w2, w3)
+    (p1 / sum, p2 / sum, p3 / sum)
 }
 
 fn edge_function(a: &Vec3, b: &Vec3, c: &Vec3) -> f32 {
     (c.x - a.x) * (b.y - a.y) - (c.y - a.y) * (b.x - a.x)
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use nalgebra_glm::{rotate, Mat4, Vec2, Vec4};
+  use crate::framebuffer::Framebuffer;
+  use crate::obj::Obj;
+
+  const WIDTH: usize = 256;
+  const HEIGHT: usize = 256;
+
+  fn screen_vertex(x: f32, y: f32) -> Vertex {
+    Vertex::new(Vec3::new(x, y, 0.5), Vec3::new(0.0, 0.0, 1.0), Vec2::new(0.0, 0.0))
+  }
+
+  // Cuenta cuántas veces se cubre cada píxel. El shader descarta todos los fragmentos, así
+  // que el tile nunca escribe profundidad y ninguno falla la prueba de z.
+  fn coverage(triangles: &[[Vertex; 3]]) -> Vec<u32> {
+    let mut framebuffer = Framebuffer::new(WIDTH, HEIGHT);
+    let mut tiles = framebuffer.tiles_mut(WIDTH.max(HEIGHT));
+    let mut counts = vec![0; WIDTH * HEIGHT];
+    let mut stats = RenderStats::default();
+    let state = RasterState::new(CullMode::None, FrontFace::CounterClockwise);
+
+    for [v1, v2, v3] in triangles {
+      triangle(v1, v2, v3, &state, &mut tiles[0], &mut stats, |fragment| {
+        counts[fragment.position.y as usize * WIDTH + fragment.position.x as usize] += 1;
+        None
+      });
+    }
+
+    counts
+  }
+
+  #[test]
+  fn shared_edges_are_covered_exactly_once() {
+    // Cuadrado partido por la diagonal, con aristas que pasan justo por centros de píxel
+    let (a, b, c, d) = (
+      screen_vertex(10.5, 10.5),
+      screen_vertex(60.5, 10.5),
+      screen_vertex(60.5, 60.5),
+      screen_vertex(10.5, 60.5),
+    );
+    let counts = coverage(&[[a.clone(), b, c.clone()], [a, c, d]]);
+
+    for y in 0..HEIGHT {
+      for x in 0..WIDTH {
+        // Las aristas izquierda y superior se incluyen; la derecha y la inferior no
+        let inside = (10..60).contains(&x) && (10..60).contains(&y);
+        assert_eq!(counts[y * WIDTH + x], inside as u32, "pixel ({}, {})", x, y);
+      }
+    }
+  }
+
+  #[test]
+  fn degenerate_triangles_are_rejected() {
+    let collinear = [screen_vertex(10.0, 10.0), screen_vertex(20.0, 20.0), screen_vertex(30.0, 30.0)];
+    let repeated = [screen_vertex(10.0, 10.0), screen_vertex(10.0, 10.0), screen_vertex(40.0, 12.0)];
+
+    assert!(coverage(&[collinear, repeated]).iter().all(|&count| count == 0));
+  }
+
+  #[test]
+  fn sphere_mesh_is_watertight() {
+    let sphere = Obj::load("assets/models/sphere.obj").expect("Failed to load obj").get_vertex_array();
+
+    // Varias orientaciones y desplazamientos de subpíxel para no depender de un caso afortunado
+    for (angle, offset) in [(0.0, 0.0), (0.7, 0.31), (2.3, 0.77), (4.1, 0.5)] {
+      let transform: Mat4 = rotate(&Mat4::identity(), angle, &Vec3::new(1.0, 0.6, 0.2).normalize());
+      let triangles: Vec<[Vertex; 3]> = sphere
+        .chunks(3)
+        .map(|chunk| {
+          [0, 1, 2].map(|i| {
+            let p = transform * Vec4::new(chunk[i].position.x, chunk[i].position.y, chunk[i].position.z, 1.0);
+            screen_vertex(128.0 + offset + p.x * 200.0, 128.0 + offset + p.y * 200.0)
+          })
+        })
+        .collect();
+
+      // Una malla cerrada y convexa cubre cada píxel de su silueta dos veces (cara frontal
+      // y trasera). Un 1 indicaría una grieta y un 3 o más, píxeles dibujados dos veces.
+      let counts = coverage(&triangles);
+      assert!(counts.iter().all(|&count| count == 0 || count == 2), "angle {}", angle);
+      assert!(counts.iter().filter(|&&count| count == 2).count() > 20_000);
+    }
+  }
+
+  #[test]
+  fn culling_follows_the_winding_and_front_face() {
+    // En pantalla Y crece hacia abajo: este orden es antihorario visto desde la cámara
+    let counter_clockwise = [screen_vertex(10.0, 10.0), screen_vertex(10.0, 40.0), screen_vertex(40.0, 10.0)];
+    let [a, b, c] = counter_clockwise.clone();
+    let clockwise = [a, c, b];
+    assert!(edge_function(&counter_clockwise[0].transformed_position, &counter_clockwise[1].transformed_position, &counter_clockwise[2].transformed_position) > 0.0);
+
+    let culled = |[v1, v2, v3]: &[Vertex; 3], cull_mode, front_face| is_culled(v1, v2, v3, &RasterState::new(cull_mode, front_face));
+    for front_face in [FrontFace::CounterClockwise, FrontFace::Clockwise] {
+      let (front, back) = match front_face {
+        FrontFace::CounterClockwise => (&counter_clockwise, &clockwise),
+        FrontFace::Clockwise => (&clockwise, &counter_clockwise),
+      };
+
+      assert!(!culled(front, CullMode::None, front_face) && !culled(back, CullMode::None, front_face));
+      assert!(!culled(front, CullMode::Back, front_face) && culled(back, CullMode::Back, front_face));
+      assert!(culled(front, CullMode::Front, front_face) && !culled(back, CullMode::Front, front_face));
+    }
+  }
+
+  #[test]
+  fn perspective_correction_weights_the_near_vertex() {
+    let (mut v1, mut v2, v3) = (screen_vertex(10.0, 10.0), screen_vertex(50.0, 10.0), screen_vertex(10.0, 50.0));
+    v1.inv_w = 1.0;
+    v2.inv_w = 0.25; // Cuatro veces más lejos
+    v2.color = Color::new(200, 0, 0);
+    let third = 1.0 / 3.0;
+
+    let (b1, b2, b3) = perspective_correct(third, third, third, &v1, &v2, &v3);
+    assert!((b1 + b2 + b3 - 1.0).abs() < 1e-6);
+    // En el centroide de pantalla, el vértice lejano pesa menos que en la afín
+    assert!(b2 < third - 0.1 && b1 > third && b3 > third, "{:?}", (b1, b2, b3));
+
+    // Y los colores de los vértices llegan al fragmento con los mismos pesos
+    let centroid_color = |interpolation| {
+      let mut framebuffer = Framebuffer::new(WIDTH, HEIGHT);
+      let mut tiles = framebuffer.tiles_mut(WIDTH.max(HEIGHT));
+      let state = RasterState { interpolation, ..RasterState::new(CullMode::None, FrontFace::CounterClockwise) };
+      let mut color = None;
+      triangle(&v1, &v2, &v3, &state, &mut tiles[0], &mut RenderStats::default(), |fragment| {
+        if fragment.position == Vec2::new(23.0, 23.0) {
+          color = Some(fragment.color);
+        }
+        None
+      });
+      color.unwrap()
+    };
+    let (affine, corrected) = (centroid_color(Interpolation::Affine), centroid_color(Interpolation::PerspectiveCorrect));
+    assert_ne!(affine, corrected);
+    assert!(corrected.to_vec3().x < affine.to_vec3().x);
+  }
+}
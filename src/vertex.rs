@@ -1,14 +1,22 @@
-use nalgebra_glm::{Vec2, Vec3};
+use nalgebra_glm::{Vec2, Vec3, Vec4};
 use crate::color::Color;
 
+/// Color de los vértices de los modelos que no traen uno propio.
+pub const DEFAULT_VERTEX_COLOR: Color = Color::new(100, 100, 100);
+
 #[derive(Clone, Debug)]
 pub struct Vertex {
   pub position: Vec3,
   pub normal: Vec3,
   pub tex_coords: Vec2,
+  pub tangent: Vec4, // Dirección de u creciente; w = ±1 da el sentido de la bitangente
   pub color: Color,
+  pub clip_position: Vec4,
+  pub inv_w: f32, // 1/w tras la proyección, para interpolar con corrección de perspectiva
   pub transformed_position: Vec3,
-  pub transformed_normal: Vec3,
+  pub transformed_normal: Vec3, // En el mundo
+  pub transformed_tangent: Vec3, // En el mundo
+  pub world_position: Vec3,
 }
 
 impl Vertex {
@@ -17,9 +25,14 @@ impl Vertex {
       position,
       normal,
       tex_coords,
-      color: Color::black(),
+      tangent: Vec4::zeros(),
+      color: DEFAULT_VERTEX_COLOR,
+      clip_position: Vec4::new(position.x, position.y, position.z, 1.0),
+      inv_w: 1.0,
       transformed_position: position,
       transformed_normal: normal,
+      transformed_tangent: Vec3::zeros(),
+      world_position: position,
     }
   }
 
@@ -28,9 +41,14 @@ impl Vertex {
       position,
       normal: Vec3::new(0.0, 0.0, 0.0),
       tex_coords: Vec2::new(0.0, 0.0),
+      tangent: Vec4::zeros(),
       color,
+      clip_position: Vec4::new(0.0, 0.0, 0.0, 1.0),
+      inv_w: 1.0,
       transformed_position: Vec3::new(0.0, 0.0, 0.0),
       transformed_normal: Vec3::new(0.0, 0.0, 0.0),
+      transformed_tangent: Vec3::new(0.0, 0.0, 0.0),
+      world_position: position,
     }
   }
 
@@ -38,6 +56,23 @@ impl Vertex {
     self.transformed_position = position;
     self.transformed_normal = normal;
   }
+
+  /// Interpola todos los atributos entre dos vértices (usado al recortar)
+  pub fn lerp(&self, other: &Vertex, t: f32) -> Vertex {
+    Vertex {
+      position: self.position.lerp(&other.position, t),
+      normal: self.normal.lerp(&other.normal, t),
+      tex_coords: self.tex_coords.lerp(&other.tex_coords, t),
+      tangent: self.tangent.lerp(&other.tangent, t),
+      color: self.color.lerp(&other.color, t),
+      clip_position: self.clip_position.lerp(&other.clip_position, t),
+      inv_w: self.inv_w + (other.inv_w - self.inv_w) * t,
+      transformed_position: self.transformed_position.lerp(&other.transformed_position, t),
+      transformed_normal: self.transformed_normal.lerp(&other.transformed_normal, t),
+      transformed_tangent: self.transformed_tangent.lerp(&other.transformed_tangent, t),
+      world_position: self.world_position.lerp(&other.world_position, t),
+    }
+  }
 }
 
 impl Default for Vertex {
@@ -46,9 +81,14 @@ impl Default for Vertex {
       position: Vec3::new(0.0, 0.0, 0.0),
       normal: Vec3::new(0.0, 1.0, 0.0),
       tex_coords: Vec2::new(0.0, 0.0),
-      color: Color::black(),
+      tangent: Vec4::new(0.0, 0.0, 0.0, 0.0),
+      color: DEFAULT_VERTEX_COLOR,
+      clip_position: Vec4::new(0.0, 0.0, 0.0, 1.0),
+      inv_w: 1.0,
       transformed_position: Vec3::new(0.0, 0.0, 0.0),
       transformed_normal: Vec3::new(0.0, 1.0, 0.0),
+      transformed_tangent: Vec3::new(0.0, 0.0, 0.0),
+      world_position: Vec3::new(0.0, 0.0, 0.0),
     }
   }
 }
@@ -0,0 +1,227 @@
+// Pruebas de regresión por imagen: renderizan escenas fijas sin ventana y las comparan
+// con las imágenes de referencia en tests/golden/.
+//
+// Para regenerar las referencias después de un cambio visual intencional:
+//     GOLDEN_UPDATE=1 cargo test golden
+// Si una comparación falla, la imagen obtenida y un mapa de diferencias quedan en
+// target/golden-diff/.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use nalgebra_glm::Vec3;
+use proyecto3_gc::color::Color;
+use proyecto3_gc::export::{load_pixels, save_pixels, ImageFormat};
+use proyecto3_gc::framebuffer::Framebuffer;
+use proyecto3_gc::light::Light;
+use proyecto3_gc::maps::SurfaceMaps;
+use proyecto3_gc::obj::Obj;
+use proyecto3_gc::params::ShaderParams;
+use proyecto3_gc::noise::SurfaceNoise;
+use proyecto3_gc::render::{render, Uniforms};
+use proyecto3_gc::scene::Scene;
+use proyecto3_gc::shaders::{ShaderRegistry, DEFAULT_VERTEX_SHADER};
+use proyecto3_gc::texture::Texture;
+use proyecto3_gc::transform::{create_model_matrix, create_perspective_matrix, create_view_matrix, create_viewport_matrix};
+use proyecto3_gc::triangle::RasterState;
+
+// Diferencia máxima permitida por canal en cada píxel
+const TOLERANCE: u8 = 3;
+
+const CLOSEUP_WIDTH: usize = 160;
+const CLOSEUP_HEIGHT: usize = 120;
+
+fn golden_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("golden")
+}
+
+fn diff_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("target").join("golden-diff")
+}
+
+fn channels(color: u32) -> [u8; 3] {
+    [(color >> 16) as u8, (color >> 8) as u8, color as u8]
+}
+
+// Compara el frame con su referencia; devuelve un mensaje de error si no coincide
+fn check_golden(name: &str, framebuffer: &Framebuffer) -> Result<(), String> {
+    let reference_path = golden_dir().join(format!("{}.png", name));
+    let actual = framebuffer.get_active_buffer();
+    let (width, height) = (framebuffer.width, framebuffer.height);
+
+    if std::env::var_os("GOLDEN_UPDATE").is_some() {
+        std::fs::create_dir_all(golden_dir()).unwrap();
+        save_pixels(actual, width, height, &reference_path, ImageFormat::Png).unwrap();
+        return Ok(());
+    }
+
+    let (reference_width, reference_height, reference) = load_pixels(&reference_path)
+        .map_err(|error| format!("{}: no se pudo leer {} ({}); genera las referencias con GOLDEN_UPDATE=1", name, reference_path.display(), error))?;
+
+    if (reference_width, reference_height) != (width, height) {
+        return Err(format!(
+            "{}: tamaño {}x{}, la referencia es {}x{}",
+            name, width, height, reference_width, reference_height
+        ));
+    }
+
+    // Mapa de diferencias: rojo donde se supera la tolerancia, la referencia atenuada en el resto
+    let mut mismatches = 0;
+    let diff: Vec<u32> = actual
+        .iter()
+        .zip(&reference)
+        .map(|(&actual, &expected)| {
+            let exceeds = channels(actual)
+                .iter()
+                .zip(channels(expected))
+                .any(|(&a, e)| a.abs_diff(e) > TOLERANCE);
+
+            if exceeds {
+                mismatches += 1;
+                0xFF0000
+            } else {
+                (expected >> 2) & 0x3F3F3F
+            }
+        })
+        .collect();
+
+    if mismatches == 0 {
+        return Ok(());
+    }
+
+    std::fs::create_dir_all(diff_dir()).unwrap();
+    let actual_path = diff_dir().join(format!("{}_actual.png", name));
+    let diff_path = diff_dir().join(format!("{}_diff.png", name));
+    save_pixels(actual, width, height, &actual_path, ImageFormat::Png).unwrap();
+    save_pixels(&diff, width, height, &diff_path, ImageFormat::Png).unwrap();
+
+    Err(format!("{}: {} píxeles fuera de tolerancia, ver {}", name, mismatches, diff_path.display()))
+}
+
+fn assert_all(results: Vec<Result<(), String>>) {
+    let failures: Vec<String> = results.into_iter().filter_map(Result::err).collect();
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
+
+// Sol arriba a la izquierda, un poco delante, para que se vea el terminador
+fn closeup_lights() -> Vec<Light> {
+    vec![
+        Light::Point { position: Vec3::new(-6.0, 4.0, 5.0), color: Color::new(255, 255, 255), intensity: 1.0 },
+        Light::Ambient { color: Color::new(255, 255, 255), intensity: 0.08 },
+    ]
+}
+
+// Tablero de 16x8 casillas de 8 texels, con el tono cambiando a lo largo de u: muestra
+// tanto el filtrado de cerca como los mipmaps hacia el borde de la esfera
+fn checker_texture() -> Arc<Texture> {
+    let (width, height) = (128, 64);
+    let pixels: Vec<u32> = (0..width * height)
+        .map(|i| {
+            let (x, y) = (i % width, i / width);
+            let hue = (x * 255 / width) as u32;
+            if (x / 8 + y / 8) % 2 == 0 { 0xF0F0F0 } else { (hue << 16) | 0x3000 | (255 - hue) }
+        })
+        .collect();
+    Arc::new(Texture::from_pixels(width, height, &pixels))
+}
+
+// Una cúpula en cada casilla del tablero, y solo las casillas claras brillan
+fn closeup_maps() -> SurfaceMaps {
+    let (width, height) = (128, 64);
+    let texel = |pixel: &dyn Fn(usize, usize) -> u32| {
+        let pixels: Vec<u32> = (0..width * height).map(|i| pixel(i % width, i / width)).collect();
+        Some(Arc::new(Texture::from_pixels(width, height, &pixels)))
+    };
+    let dome = |x: usize, y: usize| {
+        let (dx, dy) = ((x % 8) as f32 - 3.5, (y % 8) as f32 - 3.5);
+        let height = (1.0 - (dx * dx + dy * dy) / 16.0).max(0.0);
+        0x010101 * (height * 255.0) as u32
+    };
+    let gloss = |x: usize, y: usize| if (x / 8 + y / 8).is_multiple_of(2) { 0xFFFFFF } else { 0x000000 };
+    SurfaceMaps { bump: texel(&dome), bump_scale: 2.0, specular: texel(&gloss), ..SurfaceMaps::default() }
+}
+
+// Un objeto centrado, visto de cerca, con un shader y un tiempo fijos
+fn render_closeup(model: &Obj, position: Vec3, scale: f32, shader: &str, time: f32) -> Framebuffer {
+    let (width, height) = (CLOSEUP_WIDTH as f32, CLOSEUP_HEIGHT as f32);
+    let mut framebuffer = Framebuffer::new(CLOSEUP_WIDTH, CLOSEUP_HEIGHT);
+    framebuffer.clear();
+
+    let shaders = ShaderRegistry::with_builtin();
+    let params = shaders.resolve_params(shader, &ShaderParams::new());
+    let texture = Some(checker_texture());
+    let maps = if shader == "textured" { closeup_maps() } else { SurfaceMaps::default() };
+
+    for part in model.get_mesh_parts() {
+        let material = part.material.as_deref();
+        let uniforms = Uniforms {
+            model_matrix: create_model_matrix(position, scale, Vec3::new(0.3, 0.6, 0.0)),
+            view_matrix: create_view_matrix(Vec3::new(0.0, 0.0, 2.5), Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0)),
+            projection_matrix: create_perspective_matrix(width, height),
+            viewport_matrix: create_viewport_matrix(width, height),
+            time,
+            noise: SurfaceNoise::from_params(&params),
+            params: params.clone(),
+            lights: closeup_lights(),
+            camera_position: Vec3::new(0.0, 0.0, 2.5),
+            shadow: None,
+            texture: material.map_or(texture.clone(), |material| material.texture(texture.as_ref())),
+            maps: material.map_or(maps.clone(), |material| material.maps(&maps)),
+            material: part.material.clone(),
+        };
+
+        render(
+            &mut framebuffer,
+            &uniforms,
+            &part.vertices,
+            shaders.vertex(DEFAULT_VERTEX_SHADER).unwrap(),
+            shaders.fragment(shader).unwrap(),
+            &RasterState::default(),
+        );
+    }
+    framebuffer
+}
+
+#[test]
+fn golden_planet_shaders() {
+    let sphere = Obj::load("assets/models/sphere.obj").expect("Failed to load obj");
+
+    let shaders = ShaderRegistry::with_builtin();
+
+    assert_all(
+        shaders
+            .fragment_names()
+            .map(|shader| check_golden(&format!("planet_shader_{}", shader), &render_closeup(&sphere, Vec3::new(0.0, 0.0, 0.0), 1.5, shader, 0.7)))
+            .collect(),
+    );
+}
+
+#[test]
+fn golden_spaceship() {
+    let ship = Obj::load("assets/models/ship.obj").expect("Failed to load spaceship model");
+    // El modelo mide unas 60 unidades y su origen no está en el centro
+    let framebuffer = render_closeup(&ship, Vec3::new(0.4, -0.15, 0.0), 0.03, "vertex_color", 0.0);
+
+    assert_all(vec![check_golden("spaceship", &framebuffer)]);
+}
+
+#[test]
+fn golden_solar_system() {
+    assert_all(
+        [0, 100, 500]
+            .into_iter()
+            .map(|time| {
+                // Se avanza la simulación de a un paso fijo (1/60 s) sin dibujar los
+                // frames intermedios
+                let mut framebuffer = Framebuffer::new(400, 300);
+                let mut scene = Scene::solar_system(400, 300, 7);
+                for _ in 0..time {
+                    scene.update();
+                }
+
+                framebuffer.clear();
+                scene.render(&mut framebuffer);
+                check_golden(&format!("solar_system_time_{}", time), &framebuffer)
+            })
+            .collect(),
+    );
+}
@@ -0,0 +1,80 @@
+#!/usr/bin/env python3
+"""Reemplaza las coordenadas de textura de una esfera .obj por coordenadas
+equirectangulares: u es la longitud y v la latitud, como un mapa de planeta común.
+
+Así se generaron las de assets/models/sphere.obj a partir de la exportación de
+Blender. Solo lee las posiciones: las caras conservan sus vértices y normales, y cada
+esquina recibe su propia vt. Volver a correrlo sobre el resultado no lo cambia.
+
+    python3 tools/sphere_uv.py assets/models/sphere.obj
+"""
+import math
+import sys
+
+# Un vértice con |y| / r por encima de esto está en un polo
+POLE = 0.9999
+
+
+def lon_lat(vertex):
+    x, y, z = vertex
+    r = math.sqrt(x * x + y * y + z * z)
+    u = 0.5 + math.atan2(x, z) / (2 * math.pi)
+    # En el .obj v crece hacia arriba (el cargador la invierte)
+    v = 0.5 + math.asin(max(-1.0, min(1.0, y / r))) / math.pi
+    return u, v, abs(y) / r > POLE
+
+
+def face_uvs(corners):
+    uvs = [list(lon_lat(vertex)) for vertex in corners]
+
+    # Costura: si la cara cruza u = 0/1, el lado bajo pasa por encima de 1 para que la
+    # cara no recorra la textura entera al revés
+    us = [u for u, _, pole in uvs if not pole]
+    if max(us) - min(us) > 0.5:
+        for uv in uvs:
+            if not uv[2] and uv[0] < 0.5:
+                uv[0] += 1.0
+
+    # En los polos u no está definida: se usa el promedio de las otras esquinas
+    us = [u for u, _, pole in uvs if not pole]
+    for uv in uvs:
+        if uv[2]:
+            uv[0] = sum(us) / len(us)
+
+    return [(u, v) for u, v, _ in uvs]
+
+
+def main(path):
+    with open(path) as file:
+        lines = file.read().split("\n")
+    vertices = [tuple(map(float, line.split()[1:4])) for line in lines if line.startswith("v ")]
+
+    texcoords = []
+    output = []
+    for line in lines:
+        if line.startswith("vt "):
+            continue
+        if not line.startswith("f "):
+            output.append(line)
+            continue
+
+        corners = [corner.split("/") for corner in line.split()[1:]]
+        uvs = face_uvs([vertices[int(corner[0]) - 1] for corner in corners])
+        parts = []
+        for corner, uv in zip(corners, uvs):
+            texcoords.append(uv)
+            parts.append("{}/{}/{}".format(corner[0], len(texcoords), corner[2]))
+        output.append("f " + " ".join(parts))
+
+    # Las vt van antes de las normales, como las escribe Blender
+    first_normal = next(index for index, line in enumerate(output) if line.startswith("vn "))
+    output[first_normal:first_normal] = ["vt {:.6f} {:.6f}".format(u, v) for u, v in texcoords]
+
+    with open(path, "w") as file:
+        file.write("\n".join(output))
+
+
+if __name__ == "__main__":
+    if len(sys.argv) != 2:
+        sys.exit("uso: sphere_uv.py modelo.obj")
+    main(sys.argv[1])